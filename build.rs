@@ -0,0 +1,24 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Embed the git commit the binary was built from, for `zarz --version` and `/version`.
+/// Builds outside a git checkout (e.g. from crates.io) report "unknown".
+fn main() {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = Command::new("git").args(args).output().ok()?;
+        let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (output.status.success() && !value.is_empty()).then_some(value)
+    };
+
+    let commit = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    let date = git(&["log", "-1", "--format=%cs"]).unwrap_or_default();
+    println!("cargo:rustc-env=ZARZ_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=ZARZ_GIT_COMMIT_DATE={}", date);
+
+    println!("cargo:rerun-if-changed=build.rs");
+    for path in [".git/HEAD", ".git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
@@ -418,6 +418,11 @@ fn extract_expiration_from_token(jwt: &str) -> Option<i64> {
         .and_then(|payload| payload.get("exp").and_then(|v| v.as_i64()))
 }
 
+/// Unix time at which the stored ChatGPT access token expires, if it carries one.
+pub fn openai_oauth_expiry(tokens: &StoredOAuthTokens) -> Option<i64> {
+    extract_expiration_from_token(&tokens.access_token)
+}
+
 struct RefreshedTokens {
     access_token: String,
     refresh_token: String,
@@ -479,6 +484,17 @@ pub async fn ensure_openai_oauth_tokens_fresh(config: &mut Config) -> Result<boo
         return Ok(false);
     }
 
+    refresh_openai_oauth_tokens(config).await?;
+    Ok(true)
+}
+
+/// Exchange the stored refresh token for a new access token, whether or not the
+/// current one has expired (e.g. after the server rejected it).
+pub async fn refresh_openai_oauth_tokens(config: &mut Config) -> Result<()> {
+    let Some(tokens) = config.openai_oauth_tokens.clone() else {
+        anyhow::bail!("no ChatGPT OAuth tokens are stored");
+    };
+
     let refreshed = refresh_openai_access_token(&tokens.refresh_token).await?;
     let mut updated_tokens = tokens;
     updated_tokens.access_token = refreshed.access_token;
@@ -501,7 +517,7 @@ pub async fn ensure_openai_oauth_tokens_fresh(config: &mut Config) -> Result<boo
         }
     }
 
-    Ok(true)
+    Ok(())
 }
 
 pub async fn prepare_openai_environment(config: &mut Config) -> Result<()> {
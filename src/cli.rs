@@ -7,6 +7,9 @@ pub enum Provider {
     Anthropic,
     OpenAi,
     Glm,
+    /// Any server speaking the OpenAI Chat Completions API (Ollama, LM Studio, LiteLLM)
+    #[value(name = "openai-compatible")]
+    OpenAiCompatible,
 }
 
 impl Provider {
@@ -15,6 +18,7 @@ impl Provider {
             Provider::Anthropic => "anthropic",
             Provider::OpenAi => "openai",
             Provider::Glm => "glm",
+            Provider::OpenAiCompatible => "openai-compatible",
         }
     }
 
@@ -23,6 +27,7 @@ impl Provider {
             "anthropic" => Some(Provider::Anthropic),
             "openai" => Some(Provider::OpenAi),
             "glm" => Some(Provider::Glm),
+            "openai-compatible" => Some(Provider::OpenAiCompatible),
             _ => None,
         }
     }
@@ -37,25 +42,42 @@ impl Provider {
             Some(ref v) if v == "openai" => Provider::OpenAi,
             Some(ref v) if v == "anthropic" => Provider::Anthropic,
             Some(ref v) if v == "glm" => Provider::Glm,
+            Some(ref v) if v == "openai-compatible" => Provider::OpenAiCompatible,
             _ => Provider::Anthropic,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
 #[derive(Debug, Parser)]
 #[command(
     name = "zarz",
     version,
+    // Replaced by the `version` field below, which also prints the commit and models
+    disable_version_flag = true,
     about = "ZarzCLI · AI-assisted code refactoring and rewrites",
     author = "zarzet",
     long_about = "ZarzCLI - Interactive AI coding assistant\n\nUsage:\n  zarz                      Start interactive chat\n  zarz --message \"prompt\"   Send a single prompt and exit\n  zarz ask \"question\"       Ask mode (legacy)\n  zarz chat                 Chat mode (legacy)"
 )]
 pub struct Cli {
+    /// Print version, build commit and default models
+    #[arg(short = 'V', long)]
+    pub version: bool,
+
     #[arg(long, visible_alias = "msg")]
     pub message: Option<String>,
 
     #[arg(short = 'f', long)]
     pub files: Vec<PathBuf>,
+    /// Token budget shared by all --files; the largest are truncated first (0 = no limit)
+    #[arg(long, value_name = "TOKENS")]
+    pub context_budget: Option<usize>,
 
     #[command(flatten)]
     pub model_args: CommonModelArgs,
@@ -63,6 +85,41 @@ pub struct Cli {
     #[arg(long)]
     pub directory: Option<PathBuf>,
 
+    /// Skip offering to load key project files at session start
+    #[arg(long)]
+    pub no_autocontext: bool,
+    /// Show each file change and ask before writing it
+    #[arg(long)]
+    pub preview: bool,
+    /// Don't print the ASCII banner and version line on startup (also `ZARZ_NO_BANNER=1`)
+    #[arg(long, global = true)]
+    pub no_banner: bool,
+
+    /// Use the credentials and defaults of `[profiles.<name>]` from the config
+    /// (also `ZARZ_PROFILE`)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Run destructive-looking commands from the model (`rm`, `git reset --hard`, `sudo`,
+    /// `curl | sh`, ...) without asking (also `ZARZ_ALLOW_DANGEROUS=1`)
+    #[arg(long, global = true)]
+    pub allow_dangerous: bool,
+
+    /// Contact nothing but the model provider: no remote MCP servers, no `fetch_url`,
+    /// and no update check (also `ZARZ_OFFLINE=1`)
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Append JSON debug records of provider requests and tool runs to this file
+    /// (also `ZARZ_LOG_FILE`)
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// When to use colored output; `auto` disables it when stdout is not a terminal
+    /// or `NO_COLOR` is set
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -74,6 +131,28 @@ pub enum Commands {
     Chat(ChatArgs),
     Config(ConfigArgs),
     Mcp(McpArgs),
+    /// Check that each configured provider's credentials and endpoint work
+    Ping(PingArgs),
+    /// Check the config, credentials, MCP servers and config directory without
+    /// contacting any provider
+    Doctor,
+    /// Manage saved chat sessions
+    Sessions(SessionsArgs),
+}
+
+impl Commands {
+    pub fn model_args(&self) -> Option<&CommonModelArgs> {
+        match self {
+            Commands::Ask(args) => Some(&args.model_args),
+            Commands::Rewrite(args) => Some(&args.model_args),
+            Commands::Chat(args) => Some(&args.model_args),
+            Commands::Config(_)
+            | Commands::Mcp(_)
+            | Commands::Ping(_)
+            | Commands::Doctor
+            | Commands::Sessions(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -82,12 +161,23 @@ pub struct CommonModelArgs {
     pub model: Option<String>,
     #[arg(long, value_enum)]
     pub provider: Option<Provider>,
-    #[arg(long)]
+    /// Override the provider API URL (the server base URL for openai-compatible)
+    #[arg(long, visible_alias = "base-url")]
     pub endpoint: Option<String>,
     #[arg(long)]
     pub system_prompt: Option<String>,
+    /// File appended to the system prompt; defaults to `.zarz/system.md` or `AGENTS.md`
+    /// in the working directory
+    #[arg(long, value_name = "PATH")]
+    pub system_prompt_file: Option<PathBuf>,
     #[arg(long)]
     pub timeout: Option<u64>,
+    /// Sampling seed for reproducible outputs (OpenAI and GLM only)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Extra JSON fields merged into the provider request, e.g. '{"top_p":0.9}'
+    #[arg(long, value_name = "JSON", visible_alias = "model-args")]
+    pub extra: Option<String>,
 }
 
 #[derive(Debug, Args)]
@@ -98,6 +188,18 @@ pub struct AskArgs {
     pub prompt: Option<String>,
     #[arg(long)]
     pub prompt_file: Option<PathBuf>,
+    /// Print a JSON object with text, model, provider and usage instead of plain text
+    #[arg(long)]
+    pub json: bool,
+    /// Automatically include the files in DIR most relevant to the question
+    #[arg(long, value_name = "DIR")]
+    pub context_dir: Option<PathBuf>,
+    /// What to look for when picking files from --context-dir (defaults to the prompt)
+    #[arg(long, value_name = "QUERY", requires = "context_dir")]
+    pub context_query: Option<String>,
+    /// Token budget shared by all context files; the largest are truncated first (0 = no limit)
+    #[arg(long, value_name = "TOKENS")]
+    pub context_budget: Option<usize>,
     #[arg(value_name = "FILE", num_args = 0..)]
     pub context_files: Vec<PathBuf>,
 }
@@ -114,6 +216,10 @@ pub struct RewriteArgs {
     pub yes: bool,
     #[arg(long)]
     pub dry_run: bool,
+    /// Ask for `*** Begin Patch` hunks instead of whole files, so untouched parts of
+    /// large files are never regenerated
+    #[arg(long)]
+    pub patch: bool,
     #[arg(value_name = "FILE", num_args = 1..)]
     pub files: Vec<PathBuf>,
 }
@@ -124,6 +230,12 @@ pub struct ChatArgs {
     pub model_args: CommonModelArgs,
     #[arg(long)]
     pub directory: Option<PathBuf>,
+    /// Skip offering to load key project files at session start
+    #[arg(long)]
+    pub no_autocontext: bool,
+    /// Show each file change and ask before writing it
+    #[arg(long)]
+    pub preview: bool,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -134,6 +246,59 @@ pub struct ConfigArgs {
     pub show: bool,
     #[arg(long)]
     pub login_chatgpt: bool,
+    /// Encrypt stored API keys and OAuth tokens with a passphrase
+    #[arg(long)]
+    pub encrypt_keys: bool,
+    /// List the `[profiles.<name>]` tables and which one is active
+    #[arg(long)]
+    pub list_profiles: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PingArgs {
+    /// Only check this provider (default: every configured provider)
+    #[arg(long, value_enum)]
+    pub provider: Option<Provider>,
+    /// Request timeout in seconds
+    #[arg(long)]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct SessionsArgs {
+    #[command(subcommand)]
+    pub command: Option<SessionsCommands>,
+    /// Options for the default `list`, so `zarz sessions --json` works too
+    #[command(flatten)]
+    pub list: SessionsListArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SessionsListArgs {
+    /// Print a JSON array (id, title, updated_at, provider, model, message_count)
+    #[arg(long)]
+    pub json: bool,
+    /// Show only the N most recently updated sessions
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum SessionsCommands {
+    /// List saved sessions, most recently updated first (the default)
+    List(SessionsListArgs),
+    /// Delete a saved session by id (or a unique id prefix), or every session with --all
+    Rm {
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
+        /// Delete every saved session
+        #[arg(long)]
+        all: bool,
+        /// Don't ask before deleting every session
+        #[arg(short, long)]
+        yes: bool,
+    },
 }
 
 #[derive(Debug, Clone, Args)]
@@ -156,6 +321,9 @@ pub enum McpCommands {
         url: Option<String>,
         #[arg(long, default_value = "stdio")]
         transport: String,
+        /// Seconds a single tool call may run before it fails (default 60)
+        #[arg(long, value_name = "SECS")]
+        tool_timeout: Option<u64>,
     },
     List,
     Get {
@@ -164,4 +332,19 @@ pub enum McpCommands {
     Remove {
         name: String,
     },
+    /// Import servers from a Claude Desktop / Cursor style `mcpServers` JSON file
+    Import {
+        file: PathBuf,
+        /// Replace servers that already exist with the same name
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Export configured servers as portable `mcpServers` JSON
+    Export {
+        /// Write to this file instead of stdout
+        file: Option<PathBuf>,
+        /// Include env and header values instead of redacting them
+        #[arg(long)]
+        include_secrets: bool,
+    },
 }
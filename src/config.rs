@@ -2,19 +2,158 @@ use anyhow::{Context, Result};
 use crossterm::style::{Color, Stylize};
 use dialoguer::{theme::ColorfulTheme, Select};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::providers::ReasoningEffort;
+use crate::secrets;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OAuthTokens {
     pub access_token: String,
     pub refresh_token: String,
     pub id_token: String,
 }
 
+/// A named set of credentials and defaults (`[profiles.work]`), selected with
+/// `--profile` or `ZARZ_PROFILE`. Anything it leaves unset falls back to the
+/// top-level values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anthropic_api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai_api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glm_api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl Profile {
+    fn as_layer(&self) -> Config {
+        Config {
+            anthropic_api_key: self.anthropic_api_key.clone(),
+            openai_api_key: self.openai_api_key.clone(),
+            glm_api_key: self.glm_api_key.clone(),
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            ..Config::default()
+        }
+    }
+}
+
+/// Profile selected by `--profile` (exported by `main`) or `ZARZ_PROFILE`.
+pub fn active_profile() -> Option<String> {
+    std::env::var("ZARZ_PROFILE")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether `--offline` (exported by `main`) or `ZARZ_OFFLINE` asked for no network
+/// access beyond the model provider.
+pub fn offline_mode() -> bool {
+    crate::repl::env_flag("ZARZ_OFFLINE")
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenBudgets {
+    Default(u32),
+    PerModel(BTreeMap<String, u32>),
+}
+
+fn deserialize_token_budgets<'de, D>(deserializer: D) -> Result<BTreeMap<String, u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match TokenBudgets::deserialize(deserializer)? {
+        TokenBudgets::Default(budget) => BTreeMap::from([("default".to_string(), budget)]),
+        TokenBudgets::PerModel(budgets) => budgets,
+    })
+}
+
+/// A lone `default` is written back as a bare number so older versions can still read it.
+fn serialize_token_budgets<S>(budgets: &BTreeMap<String, u32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match budgets.get("default") {
+        Some(budget) if budgets.len() == 1 => serializer.serialize_u32(*budget),
+        _ => budgets.serialize(serializer),
+    }
+}
+
+/// A layer's allowlist may only narrow the one below it: its entries are kept when the
+/// base allows everything or when a base entry already covers them (`git` covers
+/// `git status`). If none survive, the base list stays in force.
+fn narrowed_allowlist(layer: &[String], base: &[String]) -> Vec<String> {
+    let narrowed: Vec<String> = layer
+        .iter()
+        .filter(|pattern| base.is_empty() || base.iter().any(|allowed| pattern.starts_with(allowed.as_str())))
+        .cloned()
+        .collect();
+    if narrowed.is_empty() {
+        base.to_vec()
+    } else {
+        narrowed
+    }
+}
+
+/// Wrappers whose argument is itself a command line.
+const NESTED_SHELLS: &[&str] = &["sh -c", "bash -c", "zsh -c", "dash -c", "eval"];
+
+/// Split a command line into the simple commands it runs, for `bash_policy_violation`.
+fn command_segments(command: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    for segment in command.split(['&', '|', ';', '\n', '(', ')', '`', '{', '}']) {
+        let segment = segment
+            .trim()
+            .trim_end_matches('$')
+            .trim_matches(['"', '\''])
+            .trim();
+        if segment.is_empty() {
+            continue;
+        }
+        if let Some(body) = NESTED_SHELLS
+            .iter()
+            .find_map(|shell| segment.strip_prefix(shell))
+            .filter(|body| body.is_empty() || body.starts_with(char::is_whitespace))
+        {
+            segments.extend(command_segments(body));
+        }
+        segments.push(segment.to_string());
+    }
+    segments
+}
+
+/// Denylists from every layer add up; a layer can't lift a command the base refuses.
+fn merged_denylist(layer: &[String], base: &[String]) -> Vec<String> {
+    let mut merged = base.to_vec();
+    for pattern in layer {
+        if !merged.contains(pattern) {
+            merged.push(pattern.clone());
+        }
+    }
+    merged
+}
+
+/// Attached to errors from a project `.zarz/config.toml`, so callers never paper over a
+/// broken project file with defaults.
+#[derive(Debug)]
+pub struct ProjectConfigError(PathBuf);
+
+impl std::fmt::Display for ProjectConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid project config {}", self.0.display())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -25,6 +164,9 @@ pub struct Config {
     pub glm_api_key: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub openai_reasoning_effort: Option<ReasoningEffort>,
+    /// GLM deep thinking on or off; unset leaves the model default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glm_thinking: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub openai_oauth_tokens: Option<OAuthTokens>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -33,6 +175,59 @@ pub struct Config {
     pub openai_organization_id: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub openai_chatgpt_account_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Output-token budgets keyed by model name (or prefix), provider name, or `default`.
+    /// A bare number, the format older configs used, is read as `default`.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_token_budgets",
+        serialize_with = "serialize_token_budgets"
+    )]
+    pub max_output_tokens: BTreeMap<String, u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Rounds of tool calls the model may make in one turn before it is told to summarize
+    /// (`ZARZ_MAX_TOOL_ITERATIONS` overrides it).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_iterations: Option<usize>,
+    /// Raw fields merged into every provider request. Tables named after a provider
+    /// (`[provider_extra.openai]`) only apply to that provider.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provider_extra: BTreeMap<String, toml::Value>,
+    /// Command prefixes the bash tool may run; when non-empty, anything else is refused.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bash_allowlist: Vec<String>,
+    /// Command prefixes the bash tool always refuses (extended by `ZARZ_BASH_DENY`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bash_denylist: Vec<String>,
+    /// Run commands that look destructive (`rm`, `git reset --hard`, `sudo`, ...) without
+    /// asking. Only honored in the global config, so a cloned project can't switch it on.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_dangerous_commands: bool,
+    /// Register the `fetch_url` tool, which lets the model GET public web pages. Only
+    /// honored in the global config, so a cloned project can't open network access.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub web_fetch: bool,
+    /// Store API keys and OAuth tokens encrypted with a passphrase-derived key.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypt_keys: bool,
+    /// Base64 salt for the key that encrypts stored credentials.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_salt: Option<String>,
+    /// Named credential sets; the active one is layered over the top-level values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Values merged in from a project `.zarz/config.toml` and the active profile,
+    /// kept so `save` never writes them back into the global config.
+    #[serde(skip)]
+    project_layer: Option<Box<Config>>,
+    /// The profile selected when loading; keys it sets win over the environment.
+    #[serde(skip)]
+    selected_profile: Option<Profile>,
 }
 
 impl Config {
@@ -42,22 +237,378 @@ impl Config {
         Ok(home.join(".zarz").join("config.toml"))
     }
 
+    /// Load the global config merged with any project config found from the current directory.
     pub fn load() -> Result<Self> {
-        let path = Self::config_path()?;
+        match std::env::current_dir() {
+            Ok(dir) => Self::load_with_project_overrides(&dir),
+            Err(_) => Self::load_global(),
+        }
+    }
+
+    /// Load the global config, then layer the active profile and the nearest
+    /// `.zarz/config.toml` found by walking up from `dir` on top of it. Project values
+    /// win over the profile, which wins over the top-level values; anything left unset
+    /// (typically API keys) falls back to the global file.
+    pub fn load_with_project_overrides(dir: &Path) -> Result<Self> {
+        Self::load_layered(&Self::config_path()?, dir, active_profile().as_deref())
+    }
+
+    fn load_layered(global_path: &Path, dir: &Path, profile_name: Option<&str>) -> Result<Self> {
+        let global = Self::load_global_from(global_path)?;
+
+        let project = match Self::find_project_config(dir, global_path) {
+            Some(project_path) => {
+                let project = fs::read_to_string(&project_path)
+                    .context("Failed to read file")
+                    .and_then(|content| Ok(toml::from_str::<Config>(&content)?))
+                    .and_then(|mut project| {
+                        let salt = project.key_salt.clone().or_else(|| global.key_salt.clone());
+                        project.decrypt_secrets(salt.as_deref())?;
+                        Ok(project)
+                    })
+                    .context(ProjectConfigError(project_path))?;
+                Some(project)
+            }
+            None => None,
+        };
+
+        let profile = match profile_name {
+            Some(name) => {
+                let profile = project
+                    .as_ref()
+                    .and_then(|project| project.profiles.get(name))
+                    .or_else(|| global.profiles.get(name))
+                    .with_context(|| {
+                        let mut names: Vec<&String> = global.profiles.keys().collect();
+                        if let Some(project) = &project {
+                            names.extend(project.profiles.keys());
+                        }
+                        names.sort();
+                        names.dedup();
+                        if names.is_empty() {
+                            format!("Unknown profile '{}' (no [profiles.<name>] tables are configured)", name)
+                        } else {
+                            let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+                            format!("Unknown profile '{}' (available: {})", name, names.join(", "))
+                        }
+                    })?;
+                Some(profile.clone())
+            }
+            None => None,
+        };
+
+        let overlay = match (project, profile.as_ref().map(Profile::as_layer)) {
+            (Some(project), Some(profile)) => project.layered_over(profile),
+            (Some(layer), None) | (None, Some(layer)) => layer,
+            (None, None) => return Ok(global),
+        };
+        let mut merged = overlay.clone().layered_over(global);
+        merged.project_layer = Some(Box::new(overlay));
+        merged.selected_profile = profile;
+        Ok(merged)
+    }
+
+    fn load_global() -> Result<Self> {
+        Self::load_global_from(&Self::config_path()?)
+    }
 
+    fn load_global_from(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::default());
         }
 
-        let content = fs::read_to_string(&path)
+        let content = fs::read_to_string(path)
             .context("Failed to read config file")?;
 
-        let config: Config = toml::from_str(&content)
+        let mut config: Config = toml::from_str(&content)
             .context("Failed to parse config file")?;
+        let salt = config.key_salt.clone();
+        config.decrypt_secrets(salt.as_deref())?;
 
         Ok(config)
     }
 
+    fn find_project_config(dir: &Path, global_path: &Path) -> Option<PathBuf> {
+        for ancestor in dir.ancestors() {
+            let candidate = ancestor.join(".zarz").join("config.toml");
+            // Walking up from inside $HOME reaches the global file itself
+            if candidate == global_path {
+                break;
+            }
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    fn layered_over(self, base: Config) -> Config {
+        Config {
+            anthropic_api_key: self.anthropic_api_key.or(base.anthropic_api_key),
+            openai_api_key: self.openai_api_key.or(base.openai_api_key),
+            glm_api_key: self.glm_api_key.or(base.glm_api_key),
+            openai_reasoning_effort: self.openai_reasoning_effort.or(base.openai_reasoning_effort),
+            glm_thinking: self.glm_thinking.or(base.glm_thinking),
+            openai_oauth_tokens: self.openai_oauth_tokens.or(base.openai_oauth_tokens),
+            openai_project_id: self.openai_project_id.or(base.openai_project_id),
+            openai_organization_id: self.openai_organization_id.or(base.openai_organization_id),
+            openai_chatgpt_account_id: self
+                .openai_chatgpt_account_id
+                .or(base.openai_chatgpt_account_id),
+            provider: self.provider.or(base.provider),
+            model: self.model.or(base.model),
+            max_output_tokens: {
+                let mut merged = base.max_output_tokens;
+                merged.extend(self.max_output_tokens);
+                merged
+            },
+            system_prompt: self.system_prompt.or(base.system_prompt),
+            max_tool_iterations: self.max_tool_iterations.or(base.max_tool_iterations),
+            provider_extra: {
+                let mut merged = base.provider_extra;
+                merged.extend(self.provider_extra);
+                merged
+            },
+            bash_allowlist: narrowed_allowlist(&self.bash_allowlist, &base.bash_allowlist),
+            bash_denylist: merged_denylist(&self.bash_denylist, &base.bash_denylist),
+            // Never taken from a project or profile layer
+            allow_dangerous_commands: base.allow_dangerous_commands,
+            web_fetch: base.web_fetch,
+            encrypt_keys: self.encrypt_keys || base.encrypt_keys,
+            key_salt: self.key_salt.or(base.key_salt),
+            profiles: {
+                let mut merged = base.profiles;
+                merged.extend(self.profiles);
+                merged
+            },
+            project_layer: None,
+            selected_profile: None,
+        }
+    }
+
+    /// Strip values that came from the project layer, restoring the global ones underneath.
+    /// Fields changed since loading are kept, so edits made at runtime still persist.
+    fn without_project_values(&self, project: &Config, global: Config) -> Config {
+        fn pick<T: Clone + PartialEq>(current: &Option<T>, project: &Option<T>, global: Option<T>) -> Option<T> {
+            if project.is_some() && current == project {
+                global
+            } else {
+                current.clone()
+            }
+        }
+
+        fn pick_list(current: &[String], layered: Vec<String>, global: Vec<String>) -> Vec<String> {
+            if current == layered {
+                global
+            } else {
+                current.to_vec()
+            }
+        }
+
+        Config {
+            anthropic_api_key: pick(&self.anthropic_api_key, &project.anthropic_api_key, global.anthropic_api_key),
+            openai_api_key: pick(&self.openai_api_key, &project.openai_api_key, global.openai_api_key),
+            glm_api_key: pick(&self.glm_api_key, &project.glm_api_key, global.glm_api_key),
+            openai_reasoning_effort: pick(
+                &self.openai_reasoning_effort,
+                &project.openai_reasoning_effort,
+                global.openai_reasoning_effort,
+            ),
+            glm_thinking: pick(&self.glm_thinking, &project.glm_thinking, global.glm_thinking),
+            openai_oauth_tokens: pick(
+                &self.openai_oauth_tokens,
+                &project.openai_oauth_tokens,
+                global.openai_oauth_tokens,
+            ),
+            openai_project_id: pick(&self.openai_project_id, &project.openai_project_id, global.openai_project_id),
+            openai_organization_id: pick(
+                &self.openai_organization_id,
+                &project.openai_organization_id,
+                global.openai_organization_id,
+            ),
+            openai_chatgpt_account_id: pick(
+                &self.openai_chatgpt_account_id,
+                &project.openai_chatgpt_account_id,
+                global.openai_chatgpt_account_id,
+            ),
+            provider: pick(&self.provider, &project.provider, global.provider),
+            model: pick(&self.model, &project.model, global.model),
+            max_output_tokens: self
+                .max_output_tokens
+                .iter()
+                .filter_map(|(key, value)| {
+                    if project.max_output_tokens.get(key) == Some(value) {
+                        global.max_output_tokens.get(key).map(|global_value| (key.clone(), *global_value))
+                    } else {
+                        Some((key.clone(), *value))
+                    }
+                })
+                .collect(),
+            system_prompt: pick(&self.system_prompt, &project.system_prompt, global.system_prompt),
+            max_tool_iterations: pick(
+                &self.max_tool_iterations,
+                &project.max_tool_iterations,
+                global.max_tool_iterations,
+            ),
+            provider_extra: self
+                .provider_extra
+                .iter()
+                .filter_map(|(key, value)| {
+                    if project.provider_extra.get(key) == Some(value) {
+                        global.provider_extra.get(key).map(|global_value| (key.clone(), global_value.clone()))
+                    } else {
+                        Some((key.clone(), value.clone()))
+                    }
+                })
+                .collect(),
+            bash_allowlist: pick_list(
+                &self.bash_allowlist,
+                narrowed_allowlist(&project.bash_allowlist, &global.bash_allowlist),
+                global.bash_allowlist,
+            ),
+            bash_denylist: pick_list(
+                &self.bash_denylist,
+                merged_denylist(&project.bash_denylist, &global.bash_denylist),
+                global.bash_denylist,
+            ),
+            allow_dangerous_commands: self.allow_dangerous_commands,
+            web_fetch: self.web_fetch,
+            encrypt_keys: if project.encrypt_keys && self.encrypt_keys {
+                global.encrypt_keys
+            } else {
+                self.encrypt_keys
+            },
+            key_salt: pick(&self.key_salt, &project.key_salt, global.key_salt),
+            profiles: self
+                .profiles
+                .iter()
+                .filter_map(|(name, profile)| {
+                    if project.profiles.get(name) == Some(profile) {
+                        global.profiles.get(name).map(|global_profile| (name.clone(), global_profile.clone()))
+                    } else {
+                        Some((name.clone(), profile.clone()))
+                    }
+                })
+                .collect(),
+            project_layer: None,
+            selected_profile: None,
+        }
+    }
+
+    /// Check a bash command against the allow/deny lists, returning why it was refused.
+    ///
+    /// Chained commands (`&&`, `||`, `;`, `|`), subshells and command substitutions
+    /// (`(...)`, `$(...)`, backticks) and `sh -c "..."`/`eval` bodies are checked piece by
+    /// piece so a denied command can't ride along behind an allowed one. This is a
+    /// prefix-matching heuristic, not a shell parser: it does not see through aliases,
+    /// functions, `xargs`, `env` or scripts, so treat it as a guard rail rather than a sandbox.
+    pub fn bash_policy_violation(&self, command: &str) -> Option<String> {
+        let mut denylist = self.bash_denylist.clone();
+        if let Ok(raw) = std::env::var("ZARZ_BASH_DENY") {
+            denylist.extend(
+                raw.split(',')
+                    .map(|pattern| pattern.trim().to_string())
+                    .filter(|pattern| !pattern.is_empty()),
+            );
+        }
+
+        let segments = command_segments(command);
+
+        for segment in &segments {
+            if let Some(pattern) = denylist.iter().find(|pattern| segment.starts_with(pattern.as_str())) {
+                return Some(format!("'{}' matches denylisted pattern '{}'", segment, pattern));
+            }
+        }
+
+        if !self.bash_allowlist.is_empty() {
+            for segment in &segments {
+                let allowed = self
+                    .bash_allowlist
+                    .iter()
+                    .any(|pattern| segment.starts_with(pattern.as_str()));
+                if !allowed {
+                    return Some(format!("'{}' is not on the bash allowlist", segment));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Output-token budget for `model`: an exact model key, then the longest matching
+    /// model prefix, then the provider name, then `default`.
+    pub fn max_output_tokens_for(&self, model: &str, provider: &crate::cli::Provider) -> Option<u32> {
+        let budgets = &self.max_output_tokens;
+        budgets
+            .get(model)
+            .or_else(|| {
+                budgets
+                    .iter()
+                    .filter(|(key, _)| model.starts_with(key.as_str()))
+                    .max_by_key(|(key, _)| key.len())
+                    .map(|(_, value)| value)
+            })
+            .or_else(|| budgets.get(provider.as_str()))
+            .or_else(|| budgets.get("default"))
+            .copied()
+    }
+
+    /// Extra request fields for `provider`: shared `[provider_extra]` keys, then that
+    /// provider's own table, then `overrides` (from `--extra`). Returns `None` when empty.
+    pub fn extra_params_for(
+        &self,
+        provider: &crate::cli::Provider,
+        overrides: Option<&crate::providers::ExtraParams>,
+    ) -> Option<crate::providers::ExtraParams> {
+        const PROVIDER_TABLES: &[&str] = &["anthropic", "openai", "glm"];
+
+        let mut params = crate::providers::ExtraParams::new();
+        let mut insert_all = |table: &toml::Table| {
+            for (key, value) in table {
+                if let Ok(value) = serde_json::to_value(value) {
+                    params.insert(key.clone(), value);
+                }
+            }
+        };
+
+        let shared: toml::Table = self
+            .provider_extra
+            .iter()
+            .filter(|(key, value)| !(PROVIDER_TABLES.contains(&key.as_str()) && value.is_table()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        insert_all(&shared);
+        if let Some(table) = self
+            .provider_extra
+            .get(provider.as_str())
+            .and_then(toml::Value::as_table)
+        {
+            insert_all(table);
+        }
+        if let Some(overrides) = overrides {
+            params.extend(overrides.clone());
+        }
+
+        (!params.is_empty()).then_some(params)
+    }
+
+    /// Model configured for `provider`, ignoring a model pinned to a different provider.
+    pub fn model_for(&self, provider: &crate::cli::Provider) -> Option<String> {
+        let matches_provider = self
+            .provider
+            .as_deref()
+            .and_then(crate::cli::Provider::from_str)
+            .map(|configured| &configured == provider)
+            .unwrap_or(true);
+
+        if matches_provider {
+            self.model.clone().filter(|model| !model.trim().is_empty())
+        } else {
+            None
+        }
+    }
+
     pub fn save(&self) -> Result<()> {
         let path = Self::config_path()?;
 
@@ -67,7 +618,15 @@ impl Config {
                 .context("Failed to create config directory")?;
         }
 
-        let content = toml::to_string_pretty(self)
+        let mut global = match &self.project_layer {
+            Some(project) => self.without_project_values(project, Self::load_global()?),
+            None => self.clone(),
+        };
+        if global.encrypt_keys {
+            // Once enabled, credentials only ever reach disk as ciphertext
+            global.encrypt_secrets()?;
+        }
+        let content = toml::to_string_pretty(&global)
             .context("Failed to serialize config")?;
 
         fs::write(&path, content)
@@ -76,11 +635,81 @@ impl Config {
         Ok(())
     }
 
+    /// Every credential field, for encrypting and decrypting in place.
+    fn secret_fields(&mut self) -> Vec<&mut String> {
+        let mut fields: Vec<&mut String> = [
+            &mut self.anthropic_api_key,
+            &mut self.openai_api_key,
+            &mut self.glm_api_key,
+        ]
+        .into_iter()
+        .filter_map(Option::as_mut)
+        .collect();
+        if let Some(tokens) = self.openai_oauth_tokens.as_mut() {
+            fields.extend([
+                &mut tokens.access_token,
+                &mut tokens.refresh_token,
+                &mut tokens.id_token,
+            ]);
+        }
+        for profile in self.profiles.values_mut() {
+            fields.extend(
+                [
+                    &mut profile.anthropic_api_key,
+                    &mut profile.openai_api_key,
+                    &mut profile.glm_api_key,
+                ]
+                .into_iter()
+                .filter_map(Option::as_mut),
+            );
+        }
+        fields
+    }
+
+    fn decrypt_secrets(&mut self, salt: Option<&str>) -> Result<()> {
+        let mut fields = self.secret_fields();
+        fields.retain(|value| secrets::is_encrypted(value));
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        let salt = salt.context("Config has encrypted API keys but no key_salt")?;
+        for value in fields {
+            *value = secrets::decrypt(value, salt)?;
+        }
+        Ok(())
+    }
+
+    fn encrypt_secrets(&mut self) -> Result<()> {
+        let salt = match &self.key_salt {
+            Some(salt) => salt.clone(),
+            None => {
+                let salt = secrets::new_salt()?;
+                self.key_salt = Some(salt.clone());
+                salt
+            }
+        };
+        for value in self.secret_fields() {
+            if !secrets::is_encrypted(value) {
+                *value = secrets::encrypt(value, &salt)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn has_api_key(&self) -> bool {
         self.anthropic_api_key.is_some()
             || self.openai_api_key.is_some()
             || self.openai_oauth_tokens.is_some()
             || self.glm_api_key.is_some()
+            || self.uses_openai_compatible()
+    }
+
+    /// OpenAI-compatible servers (Ollama, LM Studio) often need no key at all,
+    /// so selecting that provider counts as being configured.
+    pub fn uses_openai_compatible(&self) -> bool {
+        self.provider.as_deref().and_then(crate::cli::Provider::from_str)
+            == Some(crate::cli::Provider::OpenAiCompatible)
     }
 
     pub fn has_openai_auth(&self) -> bool {
@@ -88,6 +717,10 @@ impl Config {
     }
 
     pub fn interactive_setup() -> Result<Self> {
+        crate::interactive::require_interactive(
+            "Setup",
+            "Set ANTHROPIC_API_KEY, OPENAI_API_KEY or GLM_API_KEY in the environment instead, or run `zarz config` from a terminal.",
+        )?;
         let theme = ColorfulTheme::default();
 
         println!(
@@ -200,28 +833,52 @@ impl Config {
     }
 
     pub fn get_anthropic_key(&self) -> Option<String> {
-        std::env::var("ANTHROPIC_API_KEY")
-            .ok()
-            .or_else(|| self.anthropic_api_key.clone())
+        self.resolve_key("ANTHROPIC_API_KEY", &self.anthropic_api_key, |profile| &profile.anthropic_api_key)
     }
 
     pub fn get_openai_key(&self) -> Option<String> {
-        std::env::var("OPENAI_API_KEY")
-            .ok()
-            .or_else(|| self.openai_api_key.clone())
+        self.resolve_key("OPENAI_API_KEY", &self.openai_api_key, |profile| &profile.openai_api_key)
     }
 
     pub fn get_glm_key(&self) -> Option<String> {
-        std::env::var("GLM_API_KEY")
-            .ok()
-            .or_else(|| self.glm_api_key.clone())
+        self.resolve_key("GLM_API_KEY", &self.glm_api_key, |profile| &profile.glm_api_key)
+    }
+
+    /// The configured key normally yields to one exported in `var`, but not when the
+    /// active profile sets it: selecting a profile is the more deliberate choice.
+    fn resolve_key(
+        &self,
+        var: &str,
+        configured: &Option<String>,
+        in_profile: fn(&Profile) -> &Option<String>,
+    ) -> Option<String> {
+        if self.profile_sets(in_profile) && configured.is_some() {
+            return configured.clone();
+        }
+        std::env::var(var).ok().or_else(|| configured.clone())
+    }
+
+    fn profile_sets(&self, in_profile: fn(&Profile) -> &Option<String>) -> bool {
+        self.selected_profile.as_ref().is_some_and(|profile| in_profile(profile).is_some())
+    }
+
+    pub fn get_openai_compatible_key(&self) -> Option<String> {
+        std::env::var("OPENAI_COMPATIBLE_API_KEY").ok()
     }
 
     pub fn get_openai_reasoning_effort(&self) -> Option<ReasoningEffort> {
         self.openai_reasoning_effort
     }
 
+    pub fn get_glm_thinking(&self) -> Option<bool> {
+        self.glm_thinking
+    }
+
     pub fn get_default_provider(&self) -> Option<crate::cli::Provider> {
+        if let Some(provider) = self.provider.as_deref().and_then(crate::cli::Provider::from_str) {
+            return Some(provider);
+        }
+
         if self.get_anthropic_key().is_some() {
             Some(crate::cli::Provider::Anthropic)
         } else if self.has_openai_auth() {
@@ -234,15 +891,13 @@ impl Config {
     }
 
     pub fn apply_to_env(&self) {
-        if let Some(key) = &self.anthropic_api_key {
-            if std::env::var("ANTHROPIC_API_KEY").is_err() {
-                unsafe { std::env::set_var("ANTHROPIC_API_KEY", key); }
-            }
+        if let Some(key) = self.get_anthropic_key() {
+            unsafe { std::env::set_var("ANTHROPIC_API_KEY", key); }
         }
 
         // For OpenAI: prefer explicit API key, otherwise use OAuth access token
-        if let Some(key) = &self.openai_api_key {
-            if std::env::var("OPENAI_API_KEY").is_err() {
+        if self.openai_api_key.is_some() {
+            if let Some(key) = self.get_openai_key() {
                 unsafe { std::env::set_var("OPENAI_API_KEY", key); }
             }
         } else if let Some(tokens) = &self.openai_oauth_tokens {
@@ -276,10 +931,8 @@ impl Config {
             }
         }
 
-        if let Some(key) = &self.glm_api_key {
-            if std::env::var("GLM_API_KEY").is_err() {
-                unsafe { std::env::set_var("GLM_API_KEY", key); }
-            }
+        if let Some(key) = self.get_glm_key() {
+            unsafe { std::env::set_var("GLM_API_KEY", key); }
         }
     }
 
@@ -313,3 +966,98 @@ impl Config {
         Ok(removed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A global config with a `work` profile, and a project config below it.
+    fn fixture(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("zarz-config-{}-{}", name, std::process::id()));
+        let global_path = root.join("home").join(".zarz").join("config.toml");
+        let project_dir = root.join("project");
+        fs::create_dir_all(global_path.parent().unwrap()).unwrap();
+        fs::create_dir_all(project_dir.join(".zarz")).unwrap();
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::write(
+            &global_path,
+            r#"
+anthropic_api_key = "global-key"
+glm_api_key = "global-glm"
+model = "global-model"
+provider = "anthropic"
+
+[profiles.work]
+anthropic_api_key = "work-key"
+model = "work-model"
+"#,
+        )
+        .unwrap();
+        fs::write(project_dir.join(".zarz").join("config.toml"), "model = \"project-model\"\n").unwrap();
+        (global_path, project_dir)
+    }
+
+    #[test]
+    fn project_wins_over_profile_which_wins_over_global() {
+        let (global_path, project_dir) = fixture("layers");
+        let nested = project_dir.join("src");
+
+        let config = Config::load_layered(&global_path, &nested, Some("work")).unwrap();
+        assert_eq!(config.model.as_deref(), Some("project-model"));
+        assert_eq!(config.anthropic_api_key.as_deref(), Some("work-key"));
+        assert_eq!(config.glm_api_key.as_deref(), Some("global-glm"));
+        assert_eq!(config.provider.as_deref(), Some("anthropic"));
+
+        let config = Config::load_layered(&global_path, &nested, None).unwrap();
+        assert_eq!(config.model.as_deref(), Some("project-model"));
+        assert_eq!(config.anthropic_api_key.as_deref(), Some("global-key"));
+
+        let outside = global_path.parent().unwrap().parent().unwrap();
+        let config = Config::load_layered(&global_path, outside, Some("work")).unwrap();
+        assert_eq!(config.model.as_deref(), Some("work-model"));
+
+        let err = Config::load_layered(&global_path, &nested, Some("home")).unwrap_err();
+        assert!(err.to_string().contains("available: work"));
+
+        fs::remove_dir_all(global_path.ancestors().nth(3).unwrap()).ok();
+    }
+
+    #[test]
+    fn saving_drops_project_and_profile_values_but_keeps_runtime_edits() {
+        let (global_path, project_dir) = fixture("save");
+        let mut config = Config::load_layered(&global_path, &project_dir, Some("work")).unwrap();
+        config.provider = Some("glm".to_string());
+
+        let layer = config.project_layer.clone().unwrap();
+        let global = Config::load_global_from(&global_path).unwrap();
+        let saved = config.without_project_values(&layer, global);
+        assert_eq!(saved.model.as_deref(), Some("global-model"));
+        assert_eq!(saved.anthropic_api_key.as_deref(), Some("global-key"));
+        assert_eq!(saved.provider.as_deref(), Some("glm"));
+        assert!(saved.profiles.contains_key("work"));
+
+        fs::remove_dir_all(global_path.ancestors().nth(3).unwrap()).ok();
+    }
+
+    #[test]
+    fn profile_keys_win_over_the_environment() {
+        let (global_path, project_dir) = fixture("env");
+        let var = "ZARZ_TEST_PROFILE_KEY";
+        // Only this test reads the variable
+        unsafe { std::env::set_var(var, "env-key") };
+
+        let with_profile = Config::load_layered(&global_path, &project_dir, Some("work")).unwrap();
+        let key = with_profile.resolve_key(var, &with_profile.anthropic_api_key, |p| &p.anthropic_api_key);
+        assert_eq!(key.as_deref(), Some("work-key"));
+        // The profile doesn't set a GLM key, so the environment still wins for it
+        let key = with_profile.resolve_key(var, &with_profile.glm_api_key, |p| &p.glm_api_key);
+        assert_eq!(key.as_deref(), Some("env-key"));
+
+        let without_profile = Config::load_layered(&global_path, &project_dir, None).unwrap();
+        let key = without_profile.resolve_key(var, &without_profile.anthropic_api_key, |p| &p.anthropic_api_key);
+        assert_eq!(key.as_deref(), Some("env-key"));
+
+        unsafe { std::env::remove_var(var) };
+        fs::remove_dir_all(global_path.ancestors().nth(3).unwrap()).ok();
+    }
+}
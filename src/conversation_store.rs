@@ -1,15 +1,22 @@
 use std::fs;
 use std::path::{PathBuf};
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::cli::Provider;
-use crate::session::{Message, MessageRole, Session};
+use crate::session::{
+    FileChangeRecord, HistorySummary, Message, MessageRole, Session, ToolMessageKind,
+};
 use crate::config::Config;
 
+/// Held while a snapshot is written so shutdown can wait for the write to finish.
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationSnapshot {
     pub id: String,
@@ -21,6 +28,24 @@ pub struct ConversationSnapshot {
     pub working_directory: PathBuf,
     pub message_count: usize,
     pub messages: Vec<Message>,
+    #[serde(default)]
+    pub loaded_files: Vec<LoadedFileRecord>,
+    /// Files the session created, modified or deleted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_changes: Vec<FileChangeRecord>,
+}
+
+/// A file that was loaded into the session, with a digest of its contents at save time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedFileRecord {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+pub fn content_digest(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
 }
 
 #[derive(Debug, Clone)]
@@ -31,10 +56,56 @@ pub struct ConversationSummary {
     pub provider: String,
     pub model: String,
     pub message_count: usize,
+    /// Set by `search`: number of case-insensitive matches across message contents.
+    pub match_count: usize,
+    /// Set by `search`: the first line that matched, shortened for display.
+    pub snippet: Option<String>,
+}
+
+impl ConversationSummary {
+    fn from_snapshot(snapshot: ConversationSnapshot) -> Self {
+        Self {
+            id: snapshot.id,
+            title: snapshot.title,
+            updated_at: snapshot.updated_at,
+            provider: snapshot.provider,
+            model: snapshot.model,
+            message_count: snapshot.message_count,
+            match_count: 0,
+            snippet: None,
+        }
+    }
 }
 
 pub struct ConversationStore;
 
+/// Output format for `/export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+}
+
+const DEFAULT_MAX_PERSISTED_MESSAGES: usize = 2000;
+const COMPACTED_HISTORY_MARKER: &str = "[Compacted history]";
+
 impl ConversationStore {
     fn storage_dir() -> Result<PathBuf> {
         let config_path = Config::config_path()?;
@@ -87,6 +158,12 @@ impl ConversationStore {
         }
     }
 
+    /// Block until any snapshot write in progress has completed, then keep further
+    /// writes from starting. Used right before the process exits.
+    pub fn flush_and_lock() -> std::sync::MutexGuard<'static, ()> {
+        SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     pub fn save_session(session: &mut Session, provider: Provider, model: &str) -> Result<()> {
         if session.conversation_history.is_empty() {
             return Ok(());
@@ -115,6 +192,12 @@ impl ConversationStore {
         session.title = Some(title.clone());
         session.updated_at = Some(now);
 
+        let persisted = Self::compact_for_persistence(
+            &session.conversation_history,
+            session.persisted_summary.as_ref(),
+            Self::max_persisted_messages(),
+        );
+
         let snapshot = ConversationSnapshot {
             id: id.clone(),
             title,
@@ -123,30 +206,303 @@ impl ConversationStore {
             provider: provider.as_str().to_string(),
             model: model.to_string(),
             working_directory: session.working_directory.clone(),
-            message_count: session.conversation_history.len(),
-            messages: session.conversation_history.clone(),
+            message_count: persisted.len(),
+            messages: persisted,
+            loaded_files: Self::loaded_file_records(session),
+            file_changes: session.all_file_changes(),
         };
 
         let dir = Self::storage_dir()?;
         let path = dir.join(format!("{id}.json"));
         let data = serde_json::to_string_pretty(&snapshot)
             .context("Failed to serialize conversation snapshot")?;
-        fs::write(&path, data)
+        // Write to a temp file and rename so an interrupted save never leaves a torn snapshot
+        let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, data)
+            .with_context(|| format!("Failed to write conversation snapshot to {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
             .with_context(|| format!("Failed to write conversation snapshot to {}", path.display()))?;
 
         Ok(())
     }
 
+    /// Render the full conversation for sharing. Unlike saved sessions, exports are never
+    /// compacted.
+    pub fn export_session(
+        session: &Session,
+        provider: Provider,
+        model: &str,
+        format: ExportFormat,
+    ) -> Result<String> {
+        let now = Utc::now();
+        let snapshot = ConversationSnapshot {
+            id: session.storage_id.clone().unwrap_or_else(Self::generate_id),
+            title: session
+                .title
+                .clone()
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| Self::derive_title(&session.conversation_history)),
+            created_at: session.created_at.unwrap_or(now),
+            updated_at: now,
+            provider: provider.as_str().to_string(),
+            model: model.to_string(),
+            working_directory: session.working_directory.clone(),
+            message_count: session.conversation_history.len(),
+            messages: session.conversation_history.clone(),
+            loaded_files: Self::loaded_file_records(session),
+            file_changes: session.all_file_changes(),
+        };
+
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(&snapshot)
+                .context("Failed to serialize conversation export"),
+            ExportFormat::Markdown => Ok(Self::render_markdown(&snapshot)),
+        }
+    }
+
+    fn render_markdown(snapshot: &ConversationSnapshot) -> String {
+        let mut out = format!("# {}\n\n", snapshot.title);
+        out.push_str(&format!(
+            "- Provider: {}\n- Model: {}\n- Working directory: `{}`\n- Exported: {}\n",
+            snapshot.provider,
+            snapshot.model,
+            snapshot.working_directory.display(),
+            snapshot.updated_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+
+        for message in &snapshot.messages {
+            let timestamp = message
+                .created_at
+                .map(|ts| format!(" _({})_", ts.format("%Y-%m-%d %H:%M:%S UTC")))
+                .unwrap_or_default();
+
+            match &message.role {
+                MessageRole::Tool { server, tool } => {
+                    let kind = match message
+                        .metadata
+                        .as_ref()
+                        .and_then(|meta| meta.tool_message_kind.as_ref())
+                    {
+                        Some(ToolMessageKind::Command) => "call",
+                        Some(ToolMessageKind::Output) => "output",
+                        None => "result",
+                    };
+                    let fence = code_fence_for(&message.content);
+                    out.push_str(&format!(
+                        "\n<details>\n<summary>Tool {} · {}.{}{}</summary>\n\n{}\n{}\n{}\n\n</details>\n",
+                        kind,
+                        server,
+                        tool,
+                        timestamp,
+                        fence,
+                        message.content.trim_end(),
+                        fence
+                    ));
+                }
+                role => {
+                    let heading = match role {
+                        MessageRole::User => "User",
+                        MessageRole::Assistant => "Assistant",
+                        _ => "System",
+                    };
+                    out.push_str(&format!(
+                        "\n## {}{}\n\n{}\n",
+                        heading,
+                        timestamp,
+                        message.content.trim_end()
+                    ));
+                }
+            }
+        }
+
+        out
+    }
+
+    pub fn max_persisted_messages() -> usize {
+        std::env::var("ZARZ_MAX_PERSISTED_MESSAGES")
+            .ok()
+            .and_then(|raw| raw.parse::<usize>().ok())
+            .filter(|max| *max >= 2)
+            .unwrap_or(DEFAULT_MAX_PERSISTED_MESSAGES)
+    }
+
+    /// Bound the snapshot size. The `/compact` summary of the oldest messages replaces
+    /// them when it leaves the snapshot under the cap; without one, the recent messages
+    /// are kept and everything older is folded into a note listing the earlier requests.
+    fn compact_for_persistence(
+        messages: &[Message],
+        summary: Option<&HistorySummary>,
+        max_messages: usize,
+    ) -> Vec<Message> {
+        if messages.len() <= max_messages {
+            return messages.to_vec();
+        }
+
+        if let Some(summary) = summary
+            && summary.covers <= messages.len()
+            && messages.len() - summary.covers < max_messages
+        {
+            let mut compacted = Vec::with_capacity(messages.len() - summary.covers + 1);
+            compacted.push(summary.message.clone());
+            compacted.extend_from_slice(&messages[summary.covers..]);
+            return compacted;
+        }
+
+        let mut split = messages.len() - (max_messages - 1);
+        // Don't strand a tool output without the command that produced it
+        while split < messages.len()
+            && messages[split]
+                .metadata
+                .as_ref()
+                .and_then(|meta| meta.tool_message_kind.as_ref())
+                .is_some_and(|kind| matches!(kind, ToolMessageKind::Output))
+        {
+            split += 1;
+        }
+
+        let (prefix, tail) = messages.split_at(split);
+
+        let mut summary = format!(
+            "{} {} earlier messages were compacted.",
+            COMPACTED_HISTORY_MARKER,
+            prefix.len()
+        );
+        let mut requests = Vec::new();
+        for message in prefix {
+            match message.role {
+                MessageRole::System if message.content.starts_with(COMPACTED_HISTORY_MARKER) => {
+                    // Carry forward requests listed by an earlier compaction
+                    requests.extend(
+                        message
+                            .content
+                            .lines()
+                            .filter_map(|line| line.strip_prefix("- "))
+                            .map(str::to_string),
+                    );
+                }
+                MessageRole::User => {
+                    if let Some(line) = message.content.lines().find(|line| !line.trim().is_empty()) {
+                        requests.push(line.trim().chars().take(120).collect());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        const MAX_LISTED_REQUESTS: usize = 50;
+        if requests.len() > MAX_LISTED_REQUESTS {
+            requests.drain(..requests.len() - MAX_LISTED_REQUESTS);
+        }
+        if !requests.is_empty() {
+            summary.push_str(" Earlier user requests:");
+            for request in requests {
+                summary.push_str("\n- ");
+                summary.push_str(&request);
+            }
+        }
+
+        let mut compacted = Vec::with_capacity(tail.len() + 1);
+        compacted.push(Message {
+            role: MessageRole::System,
+            content: summary,
+            metadata: None,
+            created_at: prefix.last().and_then(|message| message.created_at),
+        });
+        compacted.extend_from_slice(tail);
+        compacted
+    }
+
+    fn loaded_file_records(session: &Session) -> Vec<LoadedFileRecord> {
+        let mut records: Vec<LoadedFileRecord> = session
+            .current_files
+            .iter()
+            .map(|(path, content)| LoadedFileRecord {
+                path: path.clone(),
+                sha256: content_digest(content),
+            })
+            .collect();
+        records.sort_by(|a, b| a.path.cmp(&b.path));
+        records
+    }
+
     pub fn list_summaries() -> Result<Vec<ConversationSummary>> {
+        let mut summaries: Vec<ConversationSummary> = Self::load_all_snapshots()?
+            .into_iter()
+            .map(ConversationSummary::from_snapshot)
+            .collect();
+
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.updated_at));
+        Ok(summaries)
+    }
+
+    /// Sessions whose messages contain `query` (case-insensitive), ranked by number of
+    /// matches and then by recency.
+    pub fn search(query: &str) -> Result<Vec<ConversationSummary>> {
+        const SNIPPET_CHARS: usize = 80;
+
+        let needle = query.trim().to_lowercase();
+        if needle.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut results = Vec::new();
+        for snapshot in Self::load_all_snapshots()? {
+            // Titles count too, so names given with `/save` are searchable
+            let mut match_count = snapshot.title.to_lowercase().matches(&needle).count();
+            let mut snippet = None;
+            for message in &snapshot.messages {
+                let haystack = message.content.to_lowercase();
+                let count = haystack.matches(&needle).count();
+                if count == 0 {
+                    continue;
+                }
+                match_count += count;
+                if snippet.is_none() {
+                    snippet = message
+                        .content
+                        .lines()
+                        .find(|line| line.to_lowercase().contains(&needle))
+                        .map(|line| {
+                            let line = line.trim();
+                            if line.chars().count() > SNIPPET_CHARS {
+                                let short: String = line.chars().take(SNIPPET_CHARS).collect();
+                                format!("{}…", short)
+                            } else {
+                                line.to_string()
+                            }
+                        });
+                }
+            }
+
+            if match_count > 0 {
+                let mut summary = ConversationSummary::from_snapshot(snapshot);
+                summary.match_count = match_count;
+                summary.snippet = snippet;
+                results.push(summary);
+            }
+        }
+
+        results.sort_by(|a, b| {
+            b.match_count
+                .cmp(&a.match_count)
+                .then_with(|| b.updated_at.cmp(&a.updated_at))
+        });
+        Ok(results)
+    }
+
+    fn load_all_snapshots() -> Result<Vec<ConversationSnapshot>> {
         let dir = Self::storage_dir()?;
         if !dir.exists() {
             return Ok(Vec::new());
         }
 
-        let mut summaries = Vec::new();
+        let mut snapshots = Vec::new();
         for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
             let entry = entry?;
-            if !entry.file_type()?.is_file() {
+            if !entry.file_type()?.is_file()
+                || entry.path().extension().and_then(|ext| ext.to_str()) != Some("json")
+            {
                 continue;
             }
             let content = fs::read_to_string(entry.path());
@@ -157,18 +513,66 @@ impl ConversationStore {
             let Ok(snapshot) = snapshot else {
                 continue;
             };
-            summaries.push(ConversationSummary {
-                id: snapshot.id,
-                title: snapshot.title,
-                updated_at: snapshot.updated_at,
-                provider: snapshot.provider,
-                model: snapshot.model,
-                message_count: snapshot.message_count,
-            });
+            snapshots.push(snapshot);
         }
+        Ok(snapshots)
+    }
 
-        summaries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-        Ok(summaries)
+    /// The full id of the saved session whose id is `id` or starts with it. Ambiguous
+    /// prefixes are an error rather than a guess, since callers may delete the result.
+    pub fn resolve_id(id: &str) -> Result<String> {
+        let id = id.trim();
+        if id.is_empty() {
+            bail!("No session id given");
+        }
+        let ids: Vec<String> = Self::load_all_snapshots()?
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        if ids.iter().any(|candidate| candidate == id) {
+            return Ok(id.to_string());
+        }
+        let matches: Vec<&String> = ids.iter().filter(|candidate| candidate.starts_with(id)).collect();
+        match matches.as_slice() {
+            [] => bail!("No saved session with id '{}'", id),
+            [only] => Ok((*only).clone()),
+            many => bail!("'{}' matches {} sessions; give more of the id", id, many.len()),
+        }
+    }
+
+    /// Remove a saved session by its full id.
+    pub fn delete(id: &str) -> Result<()> {
+        if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+            bail!("Invalid session id '{}'", id);
+        }
+        let path = Self::storage_dir()?.join(format!("{id}.json"));
+        if !path.is_file() {
+            bail!("No saved session with id '{}'", id);
+        }
+        let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete session file {}", path.display()))
+    }
+
+    /// Remove every saved session, returning how many were deleted.
+    pub fn delete_all() -> Result<usize> {
+        let dir = Self::storage_dir()?;
+        let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut deleted = 0;
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            // Leftover `.json.tmp` files from interrupted saves go too, but aren't counted
+            if !path.is_file() || !(name.ends_with(".json") || name.ends_with(".json.tmp")) {
+                continue;
+            }
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete session file {}", path.display()))?;
+            if name.ends_with(".json") {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
     }
 
     pub fn load_snapshot(id: &str) -> Result<ConversationSnapshot> {
@@ -181,3 +585,76 @@ impl ConversationStore {
         Ok(snapshot)
     }
 }
+
+/// A backtick fence longer than any backtick run inside `content`, so embedded code
+/// fences survive.
+fn code_fence_for(content: &str) -> String {
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: MessageRole, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            metadata: None,
+            created_at: None,
+        }
+    }
+
+    fn history(turns: usize) -> Vec<Message> {
+        (0..turns)
+            .flat_map(|turn| {
+                [
+                    message(MessageRole::User, &format!("request {}", turn)),
+                    message(MessageRole::Assistant, &format!("answer {}", turn)),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn short_history_is_saved_whole() {
+        let messages = history(3);
+        let persisted = ConversationStore::compact_for_persistence(&messages, None, 10);
+        assert_eq!(persisted.len(), 6);
+    }
+
+    #[test]
+    fn compact_summary_replaces_the_messages_it_covers() {
+        let messages = history(10);
+        let summary = HistorySummary::new(12, "Goal: fix the parser. Decided to keep the lexer.");
+
+        let persisted = ConversationStore::compact_for_persistence(&messages, Some(&summary), 10);
+
+        assert_eq!(persisted.len(), 9);
+        assert_eq!(persisted[0].role, MessageRole::System);
+        assert_eq!(
+            persisted[0].content,
+            "Summary of 12 earlier messages:\nGoal: fix the parser. Decided to keep the lexer."
+        );
+        assert_eq!(persisted[1].content, "request 6");
+        assert_eq!(persisted[8].content, "answer 9");
+    }
+
+    #[test]
+    fn stale_summary_falls_back_to_listing_requests() {
+        let messages = history(10);
+        let summary = HistorySummary::new(2, "Only the first turn.");
+
+        let persisted = ConversationStore::compact_for_persistence(&messages, Some(&summary), 10);
+
+        assert_eq!(persisted.len(), 10);
+        assert!(persisted[0].content.starts_with(COMPACTED_HISTORY_MARKER));
+        assert!(persisted[0].content.contains("- request 0"));
+        assert!(!persisted[0].content.contains("Only the first turn."));
+    }
+}
@@ -0,0 +1,275 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::providers::{CompletionResponse, TokenUsage};
+
+const REDACTED: &str = "<redacted>";
+
+/// Object keys whose values are always replaced, wherever they appear.
+const SECRET_KEYS: &[&str] = &[
+    "authorization",
+    "api_key",
+    "apikey",
+    "x-api-key",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "password",
+    "secret",
+    "client_secret",
+];
+
+/// Object keys whose values are maps of secrets (MCP server `headers` and `env`).
+const SECRET_MAP_KEYS: &[&str] = &["headers", "env"];
+
+static SECRET_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(bearer\s+)[A-Za-z0-9._~+/=-]+|\b(sk-|sk-ant-|sess-)[A-Za-z0-9_-]{8,}")
+        .expect("secret pattern is valid")
+});
+
+static LOG_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Start appending newline-delimited JSON debug records to `path` (from `--log-file`
+/// or `ZARZ_LOG_FILE`). Without a path, logging stays off.
+pub fn init(path: Option<PathBuf>) -> Result<()> {
+    let Some(path) = path.or_else(|| std::env::var_os("ZARZ_LOG_FILE").map(PathBuf::from)) else {
+        return Ok(());
+    };
+    if path.as_os_str().is_empty() {
+        return Ok(());
+    }
+
+    let file = open_log(&path)?;
+    LOG_FILE.set(Mutex::new(file)).ok();
+    Ok(())
+}
+
+fn open_log(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create log directory {}", parent.display()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {}", path.display()))
+}
+
+pub fn is_enabled() -> bool {
+    LOG_FILE.get().is_some()
+}
+
+/// The exact payload about to be sent to a provider.
+pub fn provider_request(provider: &str, endpoint: &str, payload: &Value) {
+    if !is_enabled() {
+        return;
+    }
+    write_record(json!({
+        "event": "provider_request",
+        "provider": provider,
+        "model": payload.get("model"),
+        "endpoint": endpoint,
+        "payload": payload,
+    }));
+}
+
+pub fn provider_response(
+    provider: &str,
+    model: &str,
+    result: &Result<CompletionResponse>,
+    duration: Duration,
+) {
+    if !is_enabled() {
+        return;
+    }
+    let record = match result {
+        Ok(response) => json!({
+            "event": "provider_response",
+            "provider": provider,
+            "model": model,
+            "duration_ms": duration.as_millis() as u64,
+            "stop_reason": response.stop_reason,
+            "usage": response.usage.map(usage_json),
+            "text": response.text,
+            "tool_calls": response
+                .tool_calls
+                .iter()
+                .map(|call| json!({ "id": call.id, "name": call.name, "input": call.input }))
+                .collect::<Vec<_>>(),
+        }),
+        Err(err) => json!({
+            "event": "provider_error",
+            "provider": provider,
+            "model": model,
+            "duration_ms": duration.as_millis() as u64,
+            "error": format!("{:#}", err),
+        }),
+    };
+    write_record(record);
+}
+
+pub fn tool_execution(tool: &str, input: &Value, output: &str, is_error: bool) {
+    if !is_enabled() {
+        return;
+    }
+    write_record(json!({
+        "event": "tool_execution",
+        "tool": tool,
+        "input": input,
+        "is_error": is_error,
+        "output": output,
+    }));
+}
+
+fn usage_json(usage: TokenUsage) -> Value {
+    json!({
+        "input_tokens": usage.input_tokens,
+        "output_tokens": usage.output_tokens,
+    })
+}
+
+fn write_record(record: Value) {
+    let Some(file) = LOG_FILE.get() else {
+        return;
+    };
+
+    let mut record = redact(record);
+    if let Value::Object(map) = &mut record {
+        let mut stamped = Map::new();
+        stamped.insert("timestamp".to_string(), json!(Utc::now().to_rfc3339()));
+        stamped.append(map);
+        *map = stamped;
+    }
+
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    if let Ok(line) = serde_json::to_string(&record) {
+        // Debug logging must never interrupt the session
+        writeln!(file, "{}", line).ok();
+    }
+}
+
+/// Strip credentials from a record: known secret keys, MCP header/env maps, bearer
+/// tokens, and API-key-shaped strings anywhere in the text.
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let lower = key.to_ascii_lowercase();
+                    let value = if SECRET_KEYS.contains(&lower.as_str()) {
+                        json!(REDACTED)
+                    } else if SECRET_MAP_KEYS.contains(&lower.as_str()) && value.is_object() {
+                        redact_map_values(value)
+                    } else {
+                        redact(value)
+                    };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        Value::String(text) => Value::String(redact_text(&text)),
+        other => other,
+    }
+}
+
+fn redact_map_values(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, _)| (key, json!(REDACTED)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Whether `text` contains a bearer token or an API-key-shaped string.
+pub fn contains_secret(text: &str) -> bool {
+    SECRET_PATTERN.is_match(text)
+}
+
+fn redact_text(text: &str) -> String {
+    SECRET_PATTERN
+        .replace_all(text, |caps: &regex::Captures| match caps.get(1) {
+            Some(prefix) => format!("{}{}", prefix.as_str(), REDACTED),
+            None => REDACTED.to_string(),
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_bearer_tokens_inside_strings() {
+        let redacted = redact_text("request failed: Authorization: Bearer abc.def-123_xyz was rejected");
+        assert_eq!(redacted, "request failed: Authorization: Bearer <redacted> was rejected");
+    }
+
+    #[test]
+    fn masks_api_key_shaped_strings() {
+        for key in [
+            "sk-proj1234567890abcdef",
+            "sk-ant-api03-abcdefghijkl",
+            "sess-abcdefgh12345678",
+        ] {
+            let redacted = redact_text(&format!("key {} in use", key));
+            assert_eq!(redacted, "key <redacted> in use", "{} was not masked", key);
+            assert!(contains_secret(key));
+        }
+    }
+
+    #[test]
+    fn masks_mcp_headers_and_env_whatever_the_key() {
+        let record = redact(json!({
+            "server": "docs",
+            "headers": { "X-Custom-Auth": "opaque-value", "Cookie": "session=1" },
+            "env": { "GITHUB_PERSONAL_ACCESS_TOKEN": "plain", "DEBUG": "1" },
+        }));
+
+        assert_eq!(record["server"], "docs");
+        assert_eq!(record["headers"]["X-Custom-Auth"], REDACTED);
+        assert_eq!(record["headers"]["Cookie"], REDACTED);
+        assert_eq!(record["env"]["GITHUB_PERSONAL_ACCESS_TOKEN"], REDACTED);
+        assert_eq!(record["env"]["DEBUG"], REDACTED);
+    }
+
+    #[test]
+    fn masks_secret_object_keys() {
+        let record = redact(json!({
+            "api_key": "plain-value",
+            "oauth": { "client_secret": "shh", "Authorization": "Basic dXNlcjpwYXNz" },
+        }));
+
+        assert_eq!(record["api_key"], REDACTED);
+        assert_eq!(record["oauth"]["client_secret"], REDACTED);
+        assert_eq!(record["oauth"]["Authorization"], REDACTED);
+    }
+
+    #[test]
+    fn leaves_ordinary_fields_alone() {
+        let record = json!({
+            "event": "provider_response",
+            "provider": "anthropic",
+            "model": "claude-sonnet-4-5",
+            "usage": { "input_tokens": 1200, "output_tokens": 340 },
+            "duration_ms": 812,
+            "text": "The tokenizer splits on whitespace.",
+        });
+
+        assert_eq!(redact(record.clone()), record);
+    }
+}
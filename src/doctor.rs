@@ -0,0 +1,332 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use crossterm::style::{Color, Stylize};
+
+use crate::auth;
+use crate::config::Config;
+use crate::mcp::config::REDACTED_SECRET;
+use crate::mcp::{McpConfig, McpServerConfig};
+
+/// Endpoint overrides read by the providers; each one that is set must be a URL.
+const ENDPOINT_VARS: &[&str] = &[
+    "ANTHROPIC_API_URL",
+    "OPENAI_API_URL",
+    "OPENAI_CHAT_API_URL",
+    "OPENAI_COMPATIBLE_BASE_URL",
+    "GLM_API_URL",
+];
+
+/// ChatGPT OAuth tokens this close to expiry are reported as a warning.
+const OAUTH_EXPIRY_WARNING_SECS: i64 = 10 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    status: Status,
+    label: String,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn new(status: Status, label: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            label: label.into(),
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// `zarz doctor`: check the local setup without contacting any provider, print a
+/// checklist, and fail if any check is a hard failure.
+pub fn run() -> Result<()> {
+    let mut checks = Vec::new();
+
+    let config = check_config(&mut checks);
+    if let Some(config) = &config {
+        check_providers(config, &mut checks);
+        check_oauth(config, &mut checks);
+    }
+    check_endpoints(&mut checks);
+    check_mcp(&mut checks);
+    check_config_dir(&mut checks);
+
+    for check in &checks {
+        let (mark, color) = match check.status {
+            Status::Pass => ("✓", Color::Green),
+            Status::Warn => ("!", Color::Yellow),
+            Status::Fail => ("✗", Color::Red),
+        };
+        println!("{} {:<22} {}", mark.with(color), check.label, check.detail);
+        if let Some(hint) = &check.hint {
+            println!("  {}", format!("→ {}", hint).dark_grey());
+        }
+    }
+
+    let count = |status: Status| checks.iter().filter(|check| check.status == status).count();
+    let (passed, warnings, failures) = (count(Status::Pass), count(Status::Warn), count(Status::Fail));
+    println!();
+    println!("{} passed, {} warning(s), {} failed", passed, warnings, failures);
+
+    if failures > 0 {
+        bail!("{} check(s) failed", failures);
+    }
+    Ok(())
+}
+
+fn check_config(checks: &mut Vec<Check>) -> Option<Config> {
+    let path = match Config::config_path() {
+        Ok(path) => path,
+        Err(err) => {
+            checks.push(Check::new(Status::Fail, "Config file", format!("{:#}", err)));
+            return None;
+        }
+    };
+
+    match Config::load() {
+        Ok(config) if path.exists() => {
+            checks.push(Check::new(Status::Pass, "Config file", path.display().to_string()));
+            Some(config)
+        }
+        Ok(config) => {
+            checks.push(
+                Check::new(Status::Warn, "Config file", format!("{} does not exist", path.display()))
+                    .hint("Run 'zarz config' to create it (API keys from the environment still work)"),
+            );
+            Some(config)
+        }
+        Err(err) => {
+            checks.push(
+                Check::new(Status::Fail, "Config file", format!("{:#}", err))
+                    .hint(format!("Fix the TOML in {}, or run 'zarz config --reset'", path.display())),
+            );
+            None
+        }
+    }
+}
+
+fn check_providers(config: &Config, checks: &mut Vec<Check>) {
+    let mut configured = Vec::new();
+    if config.get_anthropic_key().is_some() {
+        configured.push("anthropic");
+    }
+    if config.get_openai_key().is_some() {
+        configured.push("openai");
+    } else if config.openai_oauth_tokens.is_some() {
+        configured.push("openai (ChatGPT OAuth)");
+    }
+    if config.get_glm_key().is_some() {
+        configured.push("glm");
+    }
+    if config.uses_openai_compatible() {
+        configured.push("openai-compatible");
+    }
+
+    if configured.is_empty() {
+        checks.push(
+            Check::new(Status::Fail, "Provider credentials", "no API key or login found")
+                .hint("Run 'zarz config', or set ANTHROPIC_API_KEY, OPENAI_API_KEY or GLM_API_KEY"),
+        );
+        return;
+    }
+    checks.push(Check::new(Status::Pass, "Provider credentials", configured.join(", ")));
+
+    if let Some(provider) = config.provider.as_deref()
+        && crate::cli::Provider::from_str(provider).is_none()
+    {
+        checks.push(
+            Check::new(Status::Fail, "Default provider", format!("unknown provider '{}'", provider))
+                .hint("Use anthropic, openai, glm or openai-compatible"),
+        );
+    }
+}
+
+fn check_oauth(config: &Config, checks: &mut Vec<Check>) {
+    let Some(tokens) = &config.openai_oauth_tokens else {
+        return;
+    };
+    let Some(expires_at) = auth::openai_oauth_expiry(tokens) else {
+        checks.push(
+            Check::new(Status::Warn, "ChatGPT OAuth", "access token has no readable expiry")
+                .hint("Sign in again with /login if OpenAI requests are rejected"),
+        );
+        return;
+    };
+
+    let remaining = expires_at - Utc::now().timestamp();
+    if remaining <= 0 {
+        checks.push(
+            Check::new(Status::Warn, "ChatGPT OAuth", format!("access token expired {} ago", format_span(-remaining)))
+                .hint("It is refreshed on the next run; if that fails, sign in again with /login"),
+        );
+    } else if remaining <= OAUTH_EXPIRY_WARNING_SECS {
+        checks.push(
+            Check::new(Status::Warn, "ChatGPT OAuth", format!("access token expires in {}", format_span(remaining)))
+                .hint("It is refreshed automatically before it expires"),
+        );
+    } else {
+        checks.push(Check::new(
+            Status::Pass,
+            "ChatGPT OAuth",
+            format!("access token valid for {}", format_span(remaining)),
+        ));
+    }
+}
+
+fn check_endpoints(checks: &mut Vec<Check>) {
+    for var in ENDPOINT_VARS {
+        let Ok(value) = env::var(var) else {
+            continue;
+        };
+        match url::Url::parse(value.trim()) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+                checks.push(Check::new(Status::Pass, *var, value));
+            }
+            _ => checks.push(
+                Check::new(Status::Fail, *var, format!("'{}' is not an http(s) URL", value))
+                    .hint(format!("Unset {} or set it to a full URL such as https://host/v1", var)),
+            ),
+        }
+    }
+}
+
+fn check_mcp(checks: &mut Vec<Check>) {
+    let Ok(path) = McpConfig::config_path() else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    let config = match McpConfig::load() {
+        Ok(config) => config,
+        Err(err) => {
+            checks.push(
+                Check::new(Status::Fail, "MCP config", format!("{:#}", err))
+                    .hint(format!("Fix the JSON in {}", path.display())),
+            );
+            return;
+        }
+    };
+    checks.push(Check::new(
+        Status::Pass,
+        "MCP config",
+        format!("{} server(s) in {}", config.mcp_servers.len(), path.display()),
+    ));
+
+    let mut names: Vec<&String> = config.mcp_servers.keys().collect();
+    names.sort();
+    for name in names {
+        checks.push(check_mcp_server(name, &config.mcp_servers[name]));
+    }
+}
+
+fn check_mcp_server(name: &str, server: &McpServerConfig) -> Check {
+    let label = format!("MCP server '{}'", name);
+    let (secrets, check) = match server {
+        McpServerConfig::Stdio { command, env, .. } => {
+            let check = match find_command(command) {
+                Some(found) => Check::new(Status::Pass, label, format!("{} ({})", command, found.display())),
+                None => Check::new(Status::Fail, label, format!("command '{}' not found", command))
+                    .hint(format!("Install it or put it on PATH, or remove the server with 'zarz mcp remove {}'", name)),
+            };
+            (env, check)
+        }
+        McpServerConfig::Sse { url, headers, .. } => {
+            let check = match url::Url::parse(url) {
+                Ok(_) => Check::new(Status::Pass, label, format!("SSE {}", url)),
+                Err(err) => Check::new(Status::Fail, label, format!("invalid URL '{}': {}", url, err))
+                    .hint(format!("Re-add it with 'zarz mcp add {} --transport sse --url <url>'", name)),
+            };
+            (headers, check)
+        }
+        McpServerConfig::Http { url, headers, .. } => (
+            headers,
+            Check::new(Status::Warn, label, format!("HTTP {}", url))
+                .hint("Streamable HTTP servers are not supported yet; use the server's SSE or STDIO transport"),
+        ),
+    };
+
+    let redacted: Vec<&String> = secrets
+        .iter()
+        .flatten()
+        .filter(|(_, value)| value.as_str() == REDACTED_SECRET)
+        .map(|(key, _)| key)
+        .collect();
+    if check.status == Status::Pass && !redacted.is_empty() {
+        let keys: Vec<&str> = redacted.iter().map(|key| key.as_str()).collect();
+        return Check::new(
+            Status::Warn,
+            check.label,
+            format!("{} still {}", keys.join(", "), REDACTED_SECRET),
+        )
+        .hint("The server was imported from a redacted export; fill in the real values in mcp.json");
+    }
+    check
+}
+
+fn check_config_dir(checks: &mut Vec<Check>) {
+    let Some(dir) = Config::config_path().ok().and_then(|path| path.parent().map(Path::to_path_buf)) else {
+        return;
+    };
+    let probe = dir.join(".doctor-write-test");
+    let writable = fs::create_dir_all(&dir).and_then(|()| fs::write(&probe, b"ok"));
+    fs::remove_file(&probe).ok();
+    match writable {
+        Ok(()) => checks.push(Check::new(Status::Pass, "Config directory", format!("{} is writable", dir.display()))),
+        Err(err) => checks.push(
+            Check::new(Status::Fail, "Config directory", format!("cannot write to {}: {}", dir.display(), err))
+                .hint("Sessions, undo history and logins are stored there; fix its permissions"),
+        ),
+    }
+}
+
+/// Resolve `command` the way the MCP client will spawn it: paths are checked
+/// directly, bare names are looked up on PATH (with PATHEXT on Windows).
+fn find_command(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string())
+            .split(';')
+            .map(str::to_string)
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        extensions.iter().find_map(|ext| {
+            let candidate = dir.join(format!("{}{}", command, ext));
+            candidate.is_file().then_some(candidate)
+        })
+    })
+}
+
+fn format_span(secs: i64) -> String {
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h{}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}d{}h", s / 86_400, (s % 86_400) / 3600),
+    }
+}
@@ -0,0 +1,383 @@
+use crossterm::style::Color;
+
+/// Background behind fenced code blocks (256-color palette, a step above black).
+pub const CODE_BACKGROUND: Color = Color::AnsiValue(235);
+
+/// What a highlighted span of code is, which decides its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    String,
+    Comment,
+    Number,
+    /// Capitalized identifiers, which are types in most of the supported languages.
+    Type,
+}
+
+impl TokenKind {
+    pub fn color(self) -> Option<Color> {
+        match self {
+            TokenKind::Plain => None,
+            TokenKind::Keyword => Some(Color::Magenta),
+            TokenKind::String => Some(Color::Green),
+            TokenKind::Comment => Some(Color::DarkGrey),
+            TokenKind::Number => Some(Color::Yellow),
+            TokenKind::Type => Some(Color::Cyan),
+        }
+    }
+}
+
+/// Lexical rules for one language family; enough to color keywords, strings,
+/// comments and numbers without a full grammar.
+struct Syntax {
+    tags: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    case_insensitive: bool,
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+
+const SYNTAXES: &[Syntax] = &[
+    Syntax {
+        tags: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+        // Single quotes are left out so lifetimes don't open a string
+        quotes: &['"'],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "False", "finally", "for", "from", "global", "if",
+            "import", "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise",
+            "return", "self", "True", "try", "while", "with", "yield",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["javascript", "js", "jsx", "typescript", "ts", "tsx", "mjs", "cjs"],
+        keywords: &[
+            "async", "await", "break", "case", "catch", "class", "const", "continue", "default",
+            "delete", "do", "else", "enum", "export", "extends", "false", "finally", "for",
+            "from", "function", "if", "implements", "import", "in", "instanceof", "interface",
+            "let", "new", "null", "of", "private", "protected", "public", "readonly", "return",
+            "static", "super", "switch", "this", "throw", "true", "try", "type", "typeof",
+            "undefined", "var", "void", "while", "yield",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+        quotes: &['"', '\'', '`'],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["go", "golang"],
+        keywords: &[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else",
+            "fallthrough", "false", "for", "func", "go", "goto", "if", "import", "interface",
+            "map", "nil", "package", "range", "return", "select", "struct", "switch", "true",
+            "type", "var",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+        quotes: &['"', '\'', '`'],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &[
+            "c", "h", "cpp", "c++", "cc", "hpp", "java", "cs", "csharp", "kotlin", "kt", "swift",
+            "scala", "dart",
+        ],
+        keywords: &[
+            "auto", "bool", "boolean", "break", "case", "catch", "char", "class", "const",
+            "continue", "default", "delete", "do", "double", "else", "enum", "extends", "extern",
+            "false", "final", "float", "for", "fun", "func", "goto", "if", "implements",
+            "import", "include", "inline", "int", "interface", "let", "long", "namespace", "new",
+            "null", "nullptr", "override", "package", "private", "protected", "public",
+            "return", "short", "signed", "sizeof", "static", "struct", "switch", "template",
+            "this", "throw", "throws", "true", "try", "typedef", "union", "unsigned", "using",
+            "val", "var", "virtual", "void", "volatile", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["sh", "bash", "shell", "zsh", "console", "shellsession"],
+        keywords: &[
+            "case", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi", "for",
+            "function", "if", "in", "local", "return", "then", "until", "while",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["json", "jsonc"],
+        keywords: &["true", "false", "null"],
+        line_comments: &["//"],
+        block_comment: C_BLOCK,
+        quotes: &['"'],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["toml", "yaml", "yml", "ini", "dockerfile", "makefile", "make"],
+        keywords: &["true", "false", "null", "yes", "no"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        case_insensitive: false,
+    },
+    Syntax {
+        tags: &["sql", "postgres", "postgresql", "mysql", "sqlite"],
+        keywords: &[
+            "alter", "and", "as", "asc", "by", "create", "delete", "desc", "distinct", "drop",
+            "from", "group", "having", "index", "inner", "insert", "into", "join", "key", "left",
+            "limit", "not", "null", "on", "or", "order", "outer", "primary", "references",
+            "right", "select", "set", "table", "union", "update", "values", "where", "with",
+        ],
+        line_comments: &["--"],
+        block_comment: C_BLOCK,
+        quotes: &['\'', '"'],
+        case_insensitive: true,
+    },
+];
+
+/// Colors code one line at a time, carrying block-comment state across lines.
+pub struct Highlighter {
+    syntax: Option<&'static Syntax>,
+    in_block_comment: bool,
+}
+
+impl Highlighter {
+    /// Highlighter for a fence's language tag (` ```rust `). Unknown or missing tags
+    /// produce plain spans.
+    pub fn new(tag: &str) -> Self {
+        let tag = tag.trim().to_ascii_lowercase();
+        let syntax = SYNTAXES.iter().find(|syntax| syntax.tags.contains(&tag.as_str()));
+        Self {
+            syntax,
+            in_block_comment: false,
+        }
+    }
+
+    pub fn highlight<'a>(&mut self, line: &'a str) -> Vec<(TokenKind, &'a str)> {
+        let Some(syntax) = self.syntax else {
+            return vec![(TokenKind::Plain, line)];
+        };
+
+        // (kind, start, end) byte ranges; runs of one kind are merged so the caller
+        // switches colors less often
+        let mut ranges: Vec<(TokenKind, usize, usize)> = Vec::new();
+        let mut push = |kind: TokenKind, start: usize, end: usize| match ranges.last_mut() {
+            Some((last_kind, _, last_end)) if *last_kind == kind && *last_end == start => {
+                *last_end = end;
+            }
+            _ => ranges.push((kind, start, end)),
+        };
+
+        let mut pos = 0;
+        while pos < line.len() {
+            let rest = &line[pos..];
+
+            if self.in_block_comment {
+                let close = syntax.block_comment.map_or("", |(_, close)| close);
+                let len = match rest.find(close) {
+                    Some(idx) => {
+                        self.in_block_comment = false;
+                        idx + close.len()
+                    }
+                    None => rest.len(),
+                };
+                push(TokenKind::Comment, pos, pos + len);
+                pos += len;
+                continue;
+            }
+            if let Some((open, _)) = syntax.block_comment
+                && rest.starts_with(open)
+            {
+                self.in_block_comment = true;
+                push(TokenKind::Comment, pos, pos + open.len());
+                pos += open.len();
+                continue;
+            }
+            if syntax.line_comments.iter().any(|prefix| rest.starts_with(prefix)) {
+                push(TokenKind::Comment, pos, line.len());
+                break;
+            }
+
+            let ch = rest.chars().next().unwrap_or(' ');
+            let (kind, len) = if syntax.quotes.contains(&ch) {
+                (TokenKind::String, string_len(rest, ch))
+            } else if ch.is_ascii_digit() {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                    .unwrap_or(rest.len());
+                (TokenKind::Number, len)
+            } else if ch.is_alphabetic() || ch == '_' {
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                (classify_word(syntax, &rest[..len]), len)
+            } else {
+                (TokenKind::Plain, ch.len_utf8())
+            };
+            push(kind, pos, pos + len);
+            pos += len;
+        }
+
+        ranges
+            .into_iter()
+            .map(|(kind, start, end)| (kind, &line[start..end]))
+            .collect()
+    }
+}
+
+/// Byte length of the string literal at the start of `rest`, through its closing
+/// quote or to the end of the line.
+fn string_len(rest: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (idx, ch) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return idx + ch.len_utf8();
+        }
+    }
+    rest.len()
+}
+
+fn classify_word(syntax: &Syntax, word: &str) -> TokenKind {
+    let is_keyword = if syntax.case_insensitive {
+        syntax.keywords.iter().any(|kw| kw.eq_ignore_ascii_case(word))
+    } else {
+        syntax.keywords.contains(&word)
+    };
+    if is_keyword {
+        TokenKind::Keyword
+    } else if word.chars().next().is_some_and(char::is_uppercase) {
+        TokenKind::Type
+    } else {
+        TokenKind::Plain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of every span of `kind` in `line`.
+    fn spans_of<'a>(highlighter: &mut Highlighter, line: &'a str, kind: TokenKind) -> Vec<&'a str> {
+        highlighter
+            .highlight(line)
+            .into_iter()
+            .filter(|(span_kind, _)| *span_kind == kind)
+            .map(|(_, text)| text)
+            .collect()
+    }
+
+    #[test]
+    fn spans_cover_the_whole_line() {
+        let line = r#"let name = format!("{}!", user); // greet"#;
+        let joined: String = Highlighter::new("rust")
+            .highlight(line)
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect();
+        assert_eq!(joined, line);
+    }
+
+    #[test]
+    fn strings_keep_escaped_quotes() {
+        let mut rust = Highlighter::new("rust");
+        assert_eq!(
+            spans_of(&mut rust, r#"let s = "say \"hi\"";"#, TokenKind::String),
+            vec![r#""say \"hi\"""#]
+        );
+
+        let mut python = Highlighter::new("py");
+        assert_eq!(
+            spans_of(&mut python, r#"x = 'it\'s' + "ok""#, TokenKind::String),
+            vec![r"'it\'s'", r#""ok""#]
+        );
+    }
+
+    #[test]
+    fn unterminated_string_runs_to_end_of_line() {
+        let mut js = Highlighter::new("js");
+        assert_eq!(spans_of(&mut js, "const s = `open", TokenKind::String), vec!["`open"]);
+    }
+
+    #[test]
+    fn line_comments_end_the_line_but_not_inside_strings() {
+        let mut rust = Highlighter::new("rust");
+        assert_eq!(
+            spans_of(&mut rust, r#"let url = "http://example.com"; // home"#, TokenKind::Comment),
+            vec!["// home"]
+        );
+
+        let mut shell = Highlighter::new("bash");
+        assert_eq!(
+            spans_of(&mut shell, r##"echo "#1" # print"##, TokenKind::Comment),
+            vec!["# print"]
+        );
+    }
+
+    #[test]
+    fn block_comments_carry_across_lines() {
+        let mut c = Highlighter::new("c");
+        assert_eq!(spans_of(&mut c, "int x; /* start", TokenKind::Comment), vec!["/* start"]);
+        assert_eq!(spans_of(&mut c, "still comment", TokenKind::Comment), vec!["still comment"]);
+        let mut closing = Highlighter::new("c");
+        closing.in_block_comment = true;
+        assert_eq!(spans_of(&mut closing, "end */ int y;", TokenKind::Comment), vec!["end */"]);
+        assert_eq!(spans_of(&mut closing, "int y;", TokenKind::Keyword), vec!["int"]);
+    }
+
+    #[test]
+    fn rust_lifetimes_do_not_open_strings() {
+        let mut rust = Highlighter::new("rust");
+        let line = "fn first<'a>(items: &'a [&'a str]) -> &'a str {";
+        assert!(spans_of(&mut rust, line, TokenKind::String).is_empty());
+        assert_eq!(spans_of(&mut rust, line, TokenKind::Keyword), vec!["fn"]);
+        // The next line is unaffected by the quotes above
+        assert_eq!(spans_of(&mut rust, r#"    "done""#, TokenKind::String), vec![r#""done""#]);
+    }
+
+    #[test]
+    fn keywords_types_and_numbers() {
+        let mut rust = Highlighter::new("RS");
+        let line = "pub struct Config { retries: u32 = 3_000 }";
+        assert_eq!(spans_of(&mut rust, line, TokenKind::Keyword), vec!["pub", "struct"]);
+        assert_eq!(spans_of(&mut rust, line, TokenKind::Type), vec!["Config"]);
+        assert_eq!(spans_of(&mut rust, line, TokenKind::Number), vec!["3_000"]);
+
+        let mut sql = Highlighter::new("sql");
+        assert_eq!(spans_of(&mut sql, "SELECT id FROM users", TokenKind::Keyword), vec!["SELECT", "FROM"]);
+    }
+
+    #[test]
+    fn unknown_language_is_plain() {
+        let line = "let s = \"x\"; // y";
+        assert_eq!(Highlighter::new("brainfuck").highlight(line), vec![(TokenKind::Plain, line)]);
+        assert_eq!(Highlighter::new("").highlight(line), vec![(TokenKind::Plain, line)]);
+    }
+}
@@ -0,0 +1,160 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use crossterm::ExecutableCommand;
+use sha2::{Digest, Sha256};
+
+use crate::mcp::types::{CallToolResult, ToolContent};
+
+/// Kitty accepts base64 payloads in chunks of at most this many bytes.
+const KITTY_CHUNK: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InlineProtocol {
+    /// iTerm2's OSC 1337 (also understood by WezTerm).
+    Iterm2,
+    /// Kitty graphics protocol; only PNG can be sent directly.
+    Kitty,
+}
+
+/// Detect inline image support from the environment. `ZARZ_INLINE_IMAGES=off`
+/// disables it; `iterm`/`kitty` force a protocol.
+fn detect_protocol() -> Option<InlineProtocol> {
+    match std::env::var("ZARZ_INLINE_IMAGES")
+        .map(|raw| raw.trim().to_ascii_lowercase())
+        .as_deref()
+    {
+        Ok("0" | "off" | "false" | "no") => return None,
+        Ok("iterm" | "iterm2") => return Some(InlineProtocol::Iterm2),
+        Ok("kitty") => return Some(InlineProtocol::Kitty),
+        _ => {}
+    }
+
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    let term_program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+    if matches!(term_program.as_str(), "iTerm.app" | "WezTerm") {
+        return Some(InlineProtocol::Iterm2);
+    }
+    let term = std::env::var("TERM").unwrap_or_default();
+    if std::env::var_os("KITTY_WINDOW_ID").is_some() || term.contains("kitty") {
+        return Some(InlineProtocol::Kitty);
+    }
+    None
+}
+
+/// Show the images in an MCP tool result: saved under `~/.zarz/images` (path printed) and,
+/// where the terminal supports it, drawn inline.
+pub fn render_tool_images(result: &CallToolResult) {
+    let protocol = detect_protocol();
+
+    for item in &result.content {
+        let ToolContent::Image { data, mime_type } = item else {
+            continue;
+        };
+        let Ok(bytes) = STANDARD.decode(data.trim()) else {
+            continue;
+        };
+
+        let mut out = std::io::stdout();
+        match save_image(&bytes, mime_type) {
+            Ok(path) => {
+                out.execute(SetForegroundColor(Color::DarkGrey)).ok();
+                println!("  Image ({}, {} bytes) saved to {}", mime_type, bytes.len(), path.display());
+                out.execute(ResetColor).ok();
+            }
+            Err(err) => eprintln!("Warning: Failed to save MCP image: {}", err),
+        }
+
+        match protocol {
+            Some(InlineProtocol::Iterm2) => {
+                writeln!(
+                    out,
+                    "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:{}\x07",
+                    bytes.len(),
+                    data.trim()
+                )
+                .ok();
+            }
+            Some(InlineProtocol::Kitty) if mime_type == "image/png" => {
+                write_kitty(&mut out, data.trim());
+            }
+            _ => {}
+        }
+        out.flush().ok();
+    }
+}
+
+fn write_kitty(out: &mut impl Write, data: &str) {
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(idx + 1 < chunks.len());
+        let control = if idx == 0 {
+            format!("a=T,f=100,m={}", more)
+        } else {
+            format!("m={}", more)
+        };
+        write!(out, "\x1b_G{};{}\x1b\\", control, String::from_utf8_lossy(chunk)).ok();
+    }
+    writeln!(out).ok();
+}
+
+/// Content-addressed, so the same image returned twice reuses one file. Images are
+/// kept in a directory only the user can read, never the shared temp dir, and an
+/// existing file is reused only if it holds exactly these bytes.
+fn save_image(bytes: &[u8], mime_type: &str) -> io::Result<PathBuf> {
+    let extension = match mime_type {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        _ => "bin",
+    };
+    let digest = format!("{:x}", Sha256::digest(bytes));
+    let dir = image_dir()?;
+    let path = dir.join(format!("mcp-{}.{}", &digest[..16], extension));
+    if fs::read(&path).is_ok_and(|existing| existing == bytes) {
+        return Ok(path);
+    }
+
+    // Write under a fresh name and rename, so a half-written image is never reused
+    let tmp_path = dir.join(format!(".mcp-{}.{}.tmp", &digest[..16], std::process::id()));
+    fs::remove_file(&tmp_path).ok();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp_path)?;
+    file.write_all(bytes)?;
+    drop(file);
+    fs::rename(&tmp_path, &path)?;
+    Ok(path)
+}
+
+/// `~/.zarz/images`, created (or tightened) to be readable by the user only.
+fn image_dir() -> io::Result<PathBuf> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "home directory not found"))?;
+    let dir = home.join(".zarz").join("images");
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700);
+        builder.create(&dir)?;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    }
+    #[cfg(not(unix))]
+    builder.create(&dir)?;
+    Ok(dir)
+}
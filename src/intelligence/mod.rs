@@ -1,12 +1,14 @@
 mod rust_parser;
+mod script_parser;
 mod symbol_search;
 mod context;
 
 pub use rust_parser::RustParser;
+pub use script_parser::ScriptParser;
 pub use symbol_search::SymbolSearcher;
 pub use context::ContextBuilder;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
@@ -14,7 +16,7 @@ pub struct Symbol {
     pub name: String,
     pub kind: SymbolKind,
     pub file: PathBuf,
-    #[allow(dead_code)]
+    /// 1-based line of the definition, or 0 when the parser cannot tell (Rust).
     pub line: usize,
 }
 
@@ -24,12 +26,43 @@ pub enum SymbolKind {
     Struct,
     Enum,
     Trait,
+    Class,
     Impl,
     Module,
     Constant,
     Static,
 }
 
+/// Source languages with a symbol parser, detected by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Some(Self::JavaScript),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the symbols defined in `path` with the parser for its language.
+pub fn parse_symbols(path: &Path) -> Result<Vec<Symbol>> {
+    match Language::from_path(path) {
+        Some(Language::Rust) => RustParser::parse_file(path),
+        Some(Language::Python) => ScriptParser::parse_python(path),
+        Some(Language::JavaScript) => ScriptParser::parse_javascript(path),
+        None => Err(anyhow!("No symbol parser for {}", path.display())),
+    }
+}
+
 #[derive(Debug)]
 pub struct ProjectIntelligence {
     root: PathBuf,
@@ -46,7 +79,7 @@ impl ProjectIntelligence {
 
     #[allow(dead_code)]
     pub fn get_file_symbols(&self, file: &Path) -> Result<Vec<Symbol>> {
-        RustParser::parse_file(file)
+        parse_symbols(file)
     }
 
     pub fn get_relevant_context(&self, query: &str) -> Result<Vec<PathBuf>> {
@@ -0,0 +1,123 @@
+use super::{Symbol, SymbolKind};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+static PY_DEF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:async\s+)?def\s+([A-Za-z_]\w*)").unwrap());
+static PY_CLASS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*class\s+([A-Za-z_]\w*)").unwrap());
+// Module-level UPPER_CASE assignments, the Python convention for constants.
+static PY_CONST: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Z][A-Z0-9_]*)\s*(?::[^=]+)?=[^=]").unwrap());
+
+static JS_FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)")
+        .unwrap()
+});
+static JS_CLASS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)")
+        .unwrap()
+});
+static JS_BINDING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:export\s+)?(const|let|var)\s+([A-Za-z_$][\w$]*)\s*(?::[^=]+)?=\s*(.*)$")
+        .unwrap()
+});
+static JS_ARROW_OR_FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|[A-Za-z_$][\w$]*\s*=>)")
+        .unwrap()
+});
+static TS_INTERFACE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:declare\s+)?interface\s+([A-Za-z_$][\w$]*)").unwrap()
+});
+static TS_ENUM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:declare\s+)?(?:const\s+)?enum\s+([A-Za-z_$][\w$]*)").unwrap()
+});
+static TS_NAMESPACE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:export\s+)?(?:declare\s+)?(?:namespace|module)\s+([A-Za-z_$][\w$.]*)")
+        .unwrap()
+});
+
+/// Line-based symbol scanner for Python and JavaScript/TypeScript.
+///
+/// Unlike `RustParser` this does not build a syntax tree; it matches declaration
+/// keywords at the start of each line, which is enough to locate definitions.
+pub struct ScriptParser;
+
+impl ScriptParser {
+    pub fn parse_python(path: &Path) -> Result<Vec<Symbol>> {
+        let content = read_source(path)?;
+        let mut symbols = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let matched = if let Some(caps) = PY_DEF.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Function))
+            } else if let Some(caps) = PY_CLASS.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Class))
+            } else {
+                PY_CONST
+                    .captures(line)
+                    .map(|caps| (caps[1].to_string(), SymbolKind::Constant))
+            };
+
+            if let Some((name, kind)) = matched {
+                symbols.push(symbol(path, name, kind, index));
+            }
+        }
+
+        Ok(symbols)
+    }
+
+    /// Handles `.js`/`.jsx`/`.mjs`/`.cjs` and TypeScript sources alike; TypeScript
+    /// interfaces map to `Trait` and namespaces to `Module`.
+    pub fn parse_javascript(path: &Path) -> Result<Vec<Symbol>> {
+        let content = read_source(path)?;
+        let mut symbols = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let matched = if let Some(caps) = JS_FUNCTION.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Function))
+            } else if let Some(caps) = JS_CLASS.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Class))
+            } else if let Some(caps) = TS_INTERFACE.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Trait))
+            } else if let Some(caps) = TS_ENUM.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Enum))
+            } else if let Some(caps) = TS_NAMESPACE.captures(line) {
+                Some((caps[1].to_string(), SymbolKind::Module))
+            } else if let Some(caps) = JS_BINDING.captures(line) {
+                // Only top-level bindings (the pattern is anchored without indentation):
+                // function-valued ones are functions, other `const`s are constants.
+                if JS_ARROW_OR_FUNCTION.is_match(caps[3].trim_start()) {
+                    Some((caps[2].to_string(), SymbolKind::Function))
+                } else if &caps[1] == "const" {
+                    Some((caps[2].to_string(), SymbolKind::Constant))
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+
+            if let Some((name, kind)) = matched {
+                symbols.push(symbol(path, name, kind, index));
+            }
+        }
+
+        Ok(symbols)
+    }
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read file {}", path.display()))
+}
+
+fn symbol(path: &Path, name: String, kind: SymbolKind, index: usize) -> Symbol {
+    Symbol {
+        name,
+        kind,
+        file: path.to_path_buf(),
+        line: index + 1,
+    }
+}
@@ -1,31 +1,30 @@
-use super::{RustParser, Symbol};
+use super::{parse_symbols, Language, Symbol};
 use anyhow::Result;
 use std::path::Path;
-use walkdir::WalkDir;
+use walkdir::{DirEntry, WalkDir};
 
 pub struct SymbolSearcher;
 
+// Build output, dependencies and virtualenvs can dwarf the project itself.
+const SKIPPED_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    ".git",
+    "__pycache__",
+    ".venv",
+    "venv",
+    "dist",
+];
+
 impl SymbolSearcher {
     pub fn search(root: &Path, name: &str) -> Result<Vec<Symbol>> {
         let mut results = Vec::new();
 
-        for entry in WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() {
-                let path = entry.path();
-
-                if let Some(ext) = path.extension() {
-                    if ext == "rs" {
-                        if let Ok(symbols) = RustParser::parse_file(path) {
-                            for symbol in symbols {
-                                if symbol.name.contains(name) {
-                                    results.push(symbol);
-                                }
-                            }
-                        }
+        for path in Self::source_files(root) {
+            if let Ok(symbols) = parse_symbols(&path) {
+                for symbol in symbols {
+                    if symbol.name.contains(name) {
+                        results.push(symbol);
                     }
                 }
             }
@@ -38,23 +37,11 @@ impl SymbolSearcher {
     pub fn search_exact(root: &Path, name: &str) -> Result<Vec<Symbol>> {
         let mut results = Vec::new();
 
-        for entry in WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() {
-                let path = entry.path();
-
-                if let Some(ext) = path.extension() {
-                    if ext == "rs" {
-                        if let Ok(symbols) = RustParser::parse_file(path) {
-                            for symbol in symbols {
-                                if symbol.name == name {
-                                    results.push(symbol);
-                                }
-                            }
-                        }
+        for path in Self::source_files(root) {
+            if let Ok(symbols) = parse_symbols(&path) {
+                for symbol in symbols {
+                    if symbol.name == name {
+                        results.push(symbol);
                     }
                 }
             }
@@ -67,26 +54,14 @@ impl SymbolSearcher {
     pub fn find_references(root: &Path, symbol_name: &str) -> Result<Vec<(String, usize)>> {
         let mut references = Vec::new();
 
-        for entry in WalkDir::new(root)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() {
-                let path = entry.path();
-
-                if let Some(ext) = path.extension() {
-                    if ext == "rs" {
-                        if let Ok(content) = std::fs::read_to_string(path) {
-                            for (line_num, line) in content.lines().enumerate() {
-                                if line.contains(symbol_name) {
-                                    references.push((
-                                        format!("{}:{}", path.display(), line_num + 1),
-                                        line_num + 1,
-                                    ));
-                                }
-                            }
-                        }
+        for path in Self::source_files(root) {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                for (line_num, line) in content.lines().enumerate() {
+                    if line.contains(symbol_name) {
+                        references.push((
+                            format!("{}:{}", path.display(), line_num + 1),
+                            line_num + 1,
+                        ));
                     }
                 }
             }
@@ -94,4 +69,24 @@ impl SymbolSearcher {
 
         Ok(references)
     }
+
+    /// Files under `root` in a language with a symbol parser.
+    fn source_files(root: &Path) -> impl Iterator<Item = std::path::PathBuf> {
+        WalkDir::new(root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !Self::is_skipped_dir(entry))
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| Language::from_path(entry.path()).is_some())
+            .map(|entry| entry.into_path())
+    }
+
+    fn is_skipped_dir(entry: &DirEntry) -> bool {
+        entry.file_type().is_dir()
+            && entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| SKIPPED_DIRS.contains(&name))
+    }
 }
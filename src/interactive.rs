@@ -0,0 +1,17 @@
+use std::io::IsTerminal;
+
+use anyhow::{bail, Result};
+
+/// Whether dialoguer prompts can run: they read keys from stdin and draw on stderr.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Fail with guidance on the non-interactive alternative instead of letting dialoguer
+/// error out with a bare "not a terminal".
+pub fn require_interactive(what: &str, alternative: &str) -> Result<()> {
+    if !is_interactive() {
+        bail!("{} needs an interactive terminal. {}", what, alternative);
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::sync::Notify;
+
+/// Ctrl+C presses seen since the handler was installed.
+static PRESSES: AtomicU64 = AtomicU64::new(0);
+static PRESSED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+/// Take over Ctrl+C for the session: a press interrupts whatever the assistant is
+/// doing (the model request, MCP calls, running tools) instead of killing the process.
+/// Bash commands run in their own process group so the terminal's SIGINT doesn't
+/// reach them; their wait loop sees the press and kills the group. At the prompt,
+/// Ctrl+C is still handled by the readline editor.
+pub fn install_handler() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            PRESSES.fetch_add(1, Ordering::SeqCst);
+            PRESSED.notify_waiters();
+        }
+    });
+}
+
+/// A point to compare against with `pressed_since`; take it before starting the work.
+pub fn presses() -> u64 {
+    PRESSES.load(Ordering::SeqCst)
+}
+
+pub fn pressed_since(mark: u64) -> bool {
+    presses() != mark
+}
+
+/// Resolves at the next Ctrl+C; presses before the call don't count.
+pub async fn pressed() {
+    PRESSED.notified().await
+}
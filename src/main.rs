@@ -1,16 +1,28 @@
 mod auth;
 mod cli;
 mod config;
+mod debug_log;
+mod doctor;
+mod secrets;
 mod mcp;
 mod providers;
 mod executor;
 mod fs_ops;
+mod highlight;
 mod intelligence;
+mod inline_image;
+mod interactive;
+mod interrupt;
+mod mentions;
+mod project_prompt;
 mod repl;
 mod session;
+mod shutdown;
 mod conversation_store;
 mod update;
+mod tool_style;
 mod tools;
+mod undo;
 pub mod unified_exec;
 
 use std::{
@@ -25,11 +37,21 @@ use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 use crossterm::style::{Color, Stylize};
 use dialoguer::Confirm;
-use providers::{CompletionProvider, CompletionRequest, ProviderClient, ReasoningEffort};
+use futures::future::join_all;
+use providers::{
+    CompletionProvider, CompletionRequest, CompletionResponse, ExtraParams, ProviderClient,
+    ReasoningEffort, split_thinking_model,
+};
 use similar::{ChangeTag, TextDiff};
 
-use crate::cli::{AskArgs, ChatArgs, Cli, Commands, CommonModelArgs, ConfigArgs, McpArgs, McpCommands, Provider, RewriteArgs};
+use crate::cli::{
+    AskArgs, ChatArgs, Cli, Commands, CommonModelArgs, ConfigArgs, McpArgs, McpCommands, PingArgs, Provider,
+    RewriteArgs, SessionsArgs, SessionsCommands, SessionsListArgs,
+};
+use crate::conversation_store::ConversationStore;
+use crate::intelligence::ProjectIntelligence;
 use crate::mcp::{McpConfig, McpServerConfig};
+use crate::project_prompt::{with_project_prompt, ProjectPrompt};
 use crate::repl::Repl;
 
 // Model constants - Latest models as of 2025
@@ -63,22 +85,89 @@ Reply ONLY with updated file contents using code fences in this exact form:
 ```
 Do not include commentary before or after the fences. Always return complete file contents.
 "#;
+const DEFAULT_REWRITE_PATCH_SYSTEM_PROMPT: &str = r#"You are Zarz, an automated refactoring agent.
+Follow the user's instructions carefully.
+Reply ONLY with patches for the files you change, one block per file, in this exact form:
+*** Begin Patch
+*** Update File: relative/path.rs
+@@ -<line>,<count> +<line>,<count> @@
+ unchanged context line
+-removed line
++added line
+*** End Patch
+Prefix context lines with a space, removed lines with '-' and added lines with '+'.
+Include about three unchanged lines of context around each change, copied exactly.
+Do not include commentary before or after the patches, and do not return whole files.
+"#;
 const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 4096;
+/// Bytes of files `ask --context-dir` may include (ZARZ_CONTEXT_MAX_BYTES).
+const DEFAULT_CONTEXT_MAX_BYTES: usize = 100_000;
+/// Estimated tokens all context files may take together (--context-budget, ZARZ_CONTEXT_BUDGET).
+const DEFAULT_CONTEXT_BUDGET_TOKENS: usize = 50_000;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     if let Err(err) = run(cli).await {
+        if let Some(AlreadyReported(code)) = err.downcast_ref::<AlreadyReported>() {
+            std::process::exit(*code);
+        }
         eprintln!("Error: {err:#}");
         std::process::exit(1);
     }
     Ok(())
 }
 
-async fn run(cli: Cli) -> Result<()> {
-    // Show ASCII banner for interactive modes (not for quick ask or config commands)
-    let show_banner = cli.message.is_none()
-        && !matches!(cli.command, Some(Commands::Config(_)) | Some(Commands::Ask(_)) | Some(Commands::Rewrite(_)));
+/// A failure whose message was already printed in its own format (like `ask --json`),
+/// carrying the exit code `main` should end with.
+#[derive(Debug)]
+struct AlreadyReported(i32);
+
+impl std::fmt::Display for AlreadyReported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "exit code {}", self.0)
+    }
+}
+
+impl std::error::Error for AlreadyReported {}
+
+async fn run(mut cli: Cli) -> Result<()> {
+    if cli.version {
+        println!("{}", version_report());
+        return Ok(());
+    }
+
+    debug_log::init(cli.log_file.clone())?;
+    if let Some(profile) = &cli.profile {
+        // Every later `Config::load` (including REPL reloads) resolves the profile from here
+        unsafe { env::set_var("ZARZ_PROFILE", profile); }
+    }
+    if cli.allow_dangerous {
+        unsafe { env::set_var("ZARZ_ALLOW_DANGEROUS", "1"); }
+    }
+    if cli.offline {
+        // Read again by the MCP manager and the tool registry
+        unsafe { env::set_var("ZARZ_OFFLINE", "1"); }
+    }
+    tool_style::init_color(cli.color);
+
+    // `echo "hi" | zarz`: with no terminal to run the REPL on, the piped text is sent as
+    // a one-shot message, like `--message` (`zarz chat` still starts the REPL)
+    if cli.message.is_none() && cli.command.is_none() && !io::stdin().is_terminal() {
+        cli.message = Some(read_text_input(
+            None,
+            None,
+            true,
+            "No input on STDIN. Pipe a message (echo \"hi\" | zarz) or run zarz in a terminal to chat",
+        )?);
+    }
+
+    // Show ASCII banner for interactive modes (not for quick ask, config commands,
+    // `doctor`, or `mcp export`, whose stdout may be piped into a file)
+    let interactive = cli.message.is_none()
+        && !matches!(cli.command, Some(Commands::Config(_)) | Some(Commands::Ask(_)) | Some(Commands::Rewrite(_)) | Some(Commands::Doctor) | Some(Commands::Sessions(_)))
+        && !matches!(&cli.command, Some(Commands::Mcp(args)) if matches!(args.command, McpCommands::Export { .. }));
+    let show_banner = interactive && !cli.no_banner && !repl::env_flag("ZARZ_NO_BANNER");
 
     if show_banner {
         use crossterm::terminal;
@@ -129,11 +218,12 @@ async fn run(cli: Cli) -> Result<()> {
         println!();
     }
 
-    // Check for updates (only in interactive mode with banner)
-    if show_banner {
-        if let Ok(Some(new_version)) = update::check_for_updates().await {
-            update::print_update_notification(&new_version);
-        }
+    // Check for updates (only in interactive mode)
+    if interactive
+        && !config::offline_mode()
+        && let Ok(Some(new_version)) = update::check_for_updates().await
+    {
+        update::print_update_notification(&new_version);
     }
 
     // Check if this is a config or MCP command - they don't need API keys
@@ -144,30 +234,69 @@ async fn run(cli: Cli) -> Result<()> {
         Some(Commands::Mcp(args)) => {
             return handle_mcp(args.clone()).await;
         }
+        Some(Commands::Ping(args)) => {
+            return handle_ping(args.clone()).await;
+        }
+        Some(Commands::Doctor) => {
+            return doctor::run();
+        }
+        Some(Commands::Sessions(args)) => {
+            return handle_sessions(args.clone());
+        }
         _ => {}
     }
 
-    // Load or create configuration for all other commands (they need API keys)
-    let mut config = match config::Config::load() {
+    // Load or create configuration for all other commands (they need API keys).
+    // Project overrides are discovered from the directory chat will run in.
+    let project_dir = match &cli.command {
+        Some(Commands::Chat(args)) => args.directory.clone(),
+        _ => None,
+    }
+    .or_else(|| cli.directory.clone())
+    .or_else(|| env::current_dir().ok());
+    let loaded = match &project_dir {
+        Some(dir) => config::Config::load_with_project_overrides(dir),
+        None => config::Config::load(),
+    };
+    let keyless_provider = cli
+        .command
+        .as_ref()
+        .and_then(Commands::model_args)
+        .unwrap_or(&cli.model_args)
+        .provider
+        .clone()
+        .or_else(|| env::var("ZARZ_PROVIDER").ok().and_then(|v| Provider::from_str(&v)))
+        == Some(Provider::OpenAiCompatible);
+    let mut config = match loaded {
         Ok(cfg) => {
-            if !cfg.has_api_key() {
+            // Keys exported in the environment are enough to skip setup, and setup
+            // starts from scratch, so it only runs before a config file exists
+            if !cfg.has_api_key()
+                && cfg.get_default_provider().is_none()
+                && !keyless_provider
+                && !config::Config::config_path()?.exists()
+            {
                 // No API keys configured, run interactive setup
                 config::Config::interactive_setup()?
             } else {
                 cfg
             }
         }
-        Err(_) => {
-            // Error loading config, run interactive setup
-            config::Config::interactive_setup()?
+        Err(err) if err.downcast_ref::<config::ProjectConfigError>().is_some() => return Err(err),
+        // Only safe when there is no config file a later save could overwrite
+        Err(_) if keyless_provider && !config::Config::config_path().is_ok_and(|path| path.exists()) => {
+            config::Config::default()
         }
+        // A wrong passphrase or a mistyped profile name must not be answered with a
+        // setup, or an empty config, that overwrites the existing file
+        Err(err) => return Err(err),
     };
 
     auth::prepare_openai_environment(&mut config).await?;
 
     // If message flag is provided, run in ask mode (one-shot)
     if let Some(message) = cli.message {
-        return handle_quick_ask(message, cli.files, cli.model_args, &config).await;
+        return handle_quick_ask(message, cli.files, cli.context_budget, cli.model_args, &config).await;
     }
 
     // If subcommand is provided, use it
@@ -178,12 +307,17 @@ async fn run(cli: Cli) -> Result<()> {
             Commands::Chat(args) => handle_chat(args, &config).await,
             Commands::Config(args) => handle_config(args).await,
             Commands::Mcp(args) => handle_mcp(args).await,
+            Commands::Ping(args) => handle_ping(args).await,
+            Commands::Doctor => doctor::run(),
+            Commands::Sessions(args) => handle_sessions(args),
         }
     } else {
         // Default: start interactive chat mode
         let chat_args = ChatArgs {
             model_args: cli.model_args,
             directory: cli.directory,
+            no_autocontext: cli.no_autocontext,
+            preview: cli.preview,
         };
         handle_chat(chat_args, &config).await
     }
@@ -192,6 +326,7 @@ async fn run(cli: Cli) -> Result<()> {
 async fn handle_quick_ask(
     message: String,
     context_files: Vec<PathBuf>,
+    context_budget: Option<usize>,
     model_args: CommonModelArgs,
     config: &config::Config,
 ) -> Result<()> {
@@ -201,31 +336,32 @@ async fn handle_quick_ask(
         endpoint,
         system_prompt,
         timeout,
+        seed,
+        extra,
+        system_prompt_file,
     } = model_args;
 
     let provider_kind = provider
         .or_else(|| {
             std::env::var("ZARZ_PROVIDER")
                 .ok()
-                .and_then(|v| match v.to_ascii_lowercase().as_str() {
-                    "anthropic" => Some(Provider::Anthropic),
-                    "openai" => Some(Provider::OpenAi),
-                    "glm" => Some(Provider::Glm),
-                    _ => None,
-                })
+                .and_then(|v| Provider::from_str(&v))
         })
         .or_else(|| config.get_default_provider())
         .ok_or_else(|| anyhow!("No provider configured. Please run 'zarz config' to set up API keys."))?;
 
-    let model = resolve_model(model, &provider_kind)?;
+    let (model, thinking_budget) = resolve_model(model, &provider_kind, config)?;
     let system_prompt = system_prompt
         .or_else(|| std::env::var("ZARZ_SYSTEM_PROMPT").ok())
+        .or_else(|| config.system_prompt.clone())
         .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string());
+    let project_prompt = load_project_prompt(system_prompt_file.as_deref(), None)?;
+    let system_prompt = with_project_prompt(&system_prompt, project_prompt.as_ref());
 
     let context_section = if context_files.is_empty() {
         String::new()
     } else {
-        build_context_section(&context_files)?
+        build_context_section(&context_files, context_budget)?
     };
 
     let mut user_prompt = String::new();
@@ -239,6 +375,7 @@ async fn handle_quick_ask(
         Provider::Anthropic => config.get_anthropic_key(),
         Provider::OpenAi => config.get_openai_key(),
         Provider::Glm => config.get_glm_key(),
+        Provider::OpenAiCompatible => config.get_openai_compatible_key(),
     };
 
     let provider = ProviderClient::new(provider_kind.clone(), api_key, endpoint, timeout)?;
@@ -247,15 +384,27 @@ async fn handle_quick_ask(
     } else {
         None
     };
+    let glm_thinking = if provider_kind == Provider::Glm {
+        config.get_glm_thinking()
+    } else {
+        None
+    };
+    let extra_params = resolve_extra_params(config, &provider_kind, extra.as_deref())?;
+    let max_output_tokens = resolve_max_tokens(config, &model, &provider_kind);
     let request = CompletionRequest {
         model,
         system_prompt: Some(system_prompt),
         user_prompt,
-        max_output_tokens: resolve_max_tokens(),
+        max_output_tokens,
         temperature: resolve_temperature(),
         messages: None,
         tools: None,
         reasoning_effort,
+        seed: resolve_seed(seed),
+        extra_params,
+        thinking_budget,
+        glm_thinking,
+        stream_tokens: None,
     };
 
     let response = provider.complete(&request).await?;
@@ -264,6 +413,37 @@ async fn handle_quick_ask(
 }
 
 async fn handle_ask(args: AskArgs, config: &config::Config) -> Result<()> {
+    if !args.json {
+        let (response, _, _) = ask_once(args, config).await?;
+        println!("{}", response.text.trim());
+        return Ok(());
+    }
+
+    // In JSON mode failures are reported as a JSON object on stderr as well, so
+    // callers never have to parse the plain-text error format.
+    match ask_once(args, config).await {
+        Ok((response, model, provider)) => {
+            let output = serde_json::json!({
+                "text": response.text.trim(),
+                "model": model,
+                "provider": provider.as_str(),
+                "usage": response.usage,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            Ok(())
+        }
+        Err(err) => {
+            let output = serde_json::json!({ "error": format!("{:#}", err) });
+            eprintln!("{}", output);
+            Err(AlreadyReported(1).into())
+        }
+    }
+}
+
+async fn ask_once(
+    args: AskArgs,
+    config: &config::Config,
+) -> Result<(CompletionResponse, String, Provider)> {
     let AskArgs {
         model_args:
             CommonModelArgs {
@@ -272,30 +452,35 @@ async fn handle_ask(args: AskArgs, config: &config::Config) -> Result<()> {
                 endpoint,
                 system_prompt,
                 timeout,
+                seed,
+                extra,
+                system_prompt_file,
             },
         prompt,
         prompt_file,
-        context_files,
+        mut context_files,
+        context_dir,
+        context_query,
+        context_budget,
+        ..
     } = args;
 
     let provider_kind = provider
         .or_else(|| {
             std::env::var("ZARZ_PROVIDER")
                 .ok()
-                .and_then(|v| match v.to_ascii_lowercase().as_str() {
-                    "anthropic" => Some(Provider::Anthropic),
-                    "openai" => Some(Provider::OpenAi),
-                    "glm" => Some(Provider::Glm),
-                    _ => None,
-                })
+                .and_then(|v| Provider::from_str(&v))
         })
         .or_else(|| config.get_default_provider())
         .ok_or_else(|| anyhow!("No provider configured. Please run 'zarz config' to set up API keys."))?;
 
-    let model = resolve_model(model, &provider_kind)?;
+    let (model, thinking_budget) = resolve_model(model, &provider_kind, config)?;
     let system_prompt = system_prompt
         .or_else(|| std::env::var("ZARZ_SYSTEM_PROMPT").ok())
+        .or_else(|| config.system_prompt.clone())
         .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string());
+    let project_prompt = load_project_prompt(system_prompt_file.as_deref(), None)?;
+    let system_prompt = with_project_prompt(&system_prompt, project_prompt.as_ref());
 
     let prompt = read_text_input(
         prompt,
@@ -303,10 +488,15 @@ async fn handle_ask(args: AskArgs, config: &config::Config) -> Result<()> {
         true,
         "A prompt is required via --prompt, --prompt-file, or STDIN",
     )?;
+    if let Some(dir) = context_dir {
+        let query = context_query.as_deref().unwrap_or(&prompt);
+        let selected = select_relevant_files(&dir, query, &context_files)?;
+        context_files.extend(selected);
+    }
     let context_section = if context_files.is_empty() {
         String::new()
     } else {
-        build_context_section(&context_files)?
+        build_context_section(&context_files, context_budget)?
     };
     let mut user_prompt = String::new();
     user_prompt.push_str(prompt.trim());
@@ -319,6 +509,7 @@ async fn handle_ask(args: AskArgs, config: &config::Config) -> Result<()> {
         Provider::Anthropic => config.get_anthropic_key(),
         Provider::OpenAi => config.get_openai_key(),
         Provider::Glm => config.get_glm_key(),
+        Provider::OpenAiCompatible => config.get_openai_compatible_key(),
     };
 
     let provider = ProviderClient::new(provider_kind.clone(), api_key, endpoint, timeout)?;
@@ -327,20 +518,31 @@ async fn handle_ask(args: AskArgs, config: &config::Config) -> Result<()> {
     } else {
         None
     };
+    let glm_thinking = if provider_kind == Provider::Glm {
+        config.get_glm_thinking()
+    } else {
+        None
+    };
+    let extra_params = resolve_extra_params(config, &provider_kind, extra.as_deref())?;
+    let max_output_tokens = resolve_max_tokens(config, &model, &provider_kind);
     let request = CompletionRequest {
         model,
         system_prompt: Some(system_prompt),
         user_prompt,
-        max_output_tokens: resolve_max_tokens(),
+        max_output_tokens,
         temperature: resolve_temperature(),
         messages: None,
         tools: None,
         reasoning_effort,
+        seed: resolve_seed(seed),
+        extra_params,
+        thinking_budget,
+        glm_thinking,
+        stream_tokens: None,
     };
 
     let response = provider.complete(&request).await?;
-    println!("{}", response.text.trim());
-    Ok(())
+    Ok((response, request.model, provider_kind))
 }
 
 async fn handle_rewrite(args: RewriteArgs, config: &config::Config) -> Result<()> {
@@ -352,11 +554,15 @@ async fn handle_rewrite(args: RewriteArgs, config: &config::Config) -> Result<()
                 endpoint,
                 system_prompt,
                 timeout,
+                seed,
+                extra,
+                system_prompt_file,
             },
         instructions,
         instructions_file,
         yes,
         dry_run,
+        patch,
         files,
     } = args;
 
@@ -364,20 +570,29 @@ async fn handle_rewrite(args: RewriteArgs, config: &config::Config) -> Result<()
         .or_else(|| {
             std::env::var("ZARZ_PROVIDER")
                 .ok()
-                .and_then(|v| match v.to_ascii_lowercase().as_str() {
-                    "anthropic" => Some(Provider::Anthropic),
-                    "openai" => Some(Provider::OpenAi),
-                    "glm" => Some(Provider::Glm),
-                    _ => None,
-                })
+                .and_then(|v| Provider::from_str(&v))
         })
         .or_else(|| config.get_default_provider())
         .ok_or_else(|| anyhow!("No provider configured. Please run 'zarz config' to set up API keys."))?;
 
-    let model = resolve_model(model, &provider_kind)?;
+    let (model, thinking_budget) = resolve_model(model, &provider_kind, config)?;
     let system_prompt = system_prompt
-        .or_else(|| std::env::var("ZARZ_REWRITE_SYSTEM_PROMPT").ok())
-        .unwrap_or_else(|| DEFAULT_REWRITE_SYSTEM_PROMPT.to_string());
+        .or_else(|| {
+            if patch {
+                std::env::var("ZARZ_REWRITE_PATCH_SYSTEM_PROMPT").ok()
+            } else {
+                std::env::var("ZARZ_REWRITE_SYSTEM_PROMPT").ok()
+            }
+        })
+        .unwrap_or_else(|| {
+            if patch {
+                DEFAULT_REWRITE_PATCH_SYSTEM_PROMPT.to_string()
+            } else {
+                DEFAULT_REWRITE_SYSTEM_PROMPT.to_string()
+            }
+        });
+    let project_prompt = load_project_prompt(system_prompt_file.as_deref(), None)?;
+    let system_prompt = with_project_prompt(&system_prompt, project_prompt.as_ref());
 
     let instructions = read_text_input(
         instructions,
@@ -394,12 +609,13 @@ async fn handle_rewrite(args: RewriteArgs, config: &config::Config) -> Result<()
         files_with_content.push((path.clone(), content));
     }
 
-    let user_prompt = build_rewrite_prompt(&instructions, &files_with_content);
+    let user_prompt = build_rewrite_prompt(&instructions, &files_with_content, patch);
 
     let api_key = match provider_kind {
         Provider::Anthropic => config.get_anthropic_key(),
         Provider::OpenAi => config.get_openai_key(),
         Provider::Glm => config.get_glm_key(),
+        Provider::OpenAiCompatible => config.get_openai_compatible_key(),
     };
 
     let provider = ProviderClient::new(provider_kind.clone(), api_key, endpoint, timeout)?;
@@ -408,34 +624,51 @@ async fn handle_rewrite(args: RewriteArgs, config: &config::Config) -> Result<()
     } else {
         None
     };
+    let glm_thinking = if provider_kind == Provider::Glm {
+        config.get_glm_thinking()
+    } else {
+        None
+    };
+    let extra_params = resolve_extra_params(config, &provider_kind, extra.as_deref())?;
+    let max_output_tokens = resolve_max_tokens(config, &model, &provider_kind);
     let request = CompletionRequest {
         model,
         system_prompt: Some(system_prompt),
         user_prompt,
-        max_output_tokens: resolve_max_tokens(),
+        max_output_tokens,
         temperature: resolve_rewrite_temperature(),
         messages: None,
         tools: None,
         reasoning_effort,
+        seed: resolve_seed(seed),
+        extra_params,
+        thinking_budget,
+        glm_thinking,
+        stream_tokens: None,
     };
 
     let response = provider.complete(&request).await?;
-    let plan = parse_file_blocks(&response.text);
-    if plan.is_empty() {
-        bail!("Model response did not include any ` ```file:...` blocks to apply");
-    }
-
-    let mut diffs = Vec::new();
-    for (path, original) in &files_with_content {
-        let normalized = normalize_path(path);
-        let Some(new_content) = plan.get(&normalized).or_else(|| plan.get(path)) else {
-            bail!(
-                "Model response did not provide updated contents for {}",
-                path.display()
-            );
-        };
-        diffs.push((path.clone(), original.clone(), new_content.clone()));
-    }
+    let diffs = if patch {
+        patched_files(&response.text, &files_with_content)?
+    } else {
+        let plan = parse_file_blocks(&response.text);
+        if plan.is_empty() {
+            bail!("Model response did not include any ` ```file:...` blocks to apply");
+        }
+
+        let mut diffs = Vec::new();
+        for (path, original) in &files_with_content {
+            let normalized = normalize_path(path);
+            let Some(new_content) = plan.get(&normalized).or_else(|| plan.get(path)) else {
+                bail!(
+                    "Model response did not provide updated contents for {}",
+                    path.display()
+                );
+            };
+            diffs.push((path.clone(), original.clone(), new_content.clone()));
+        }
+        diffs
+    };
 
     let mut any_changes = false;
     for (path, before, after) in &diffs {
@@ -459,7 +692,7 @@ async fn handle_rewrite(args: RewriteArgs, config: &config::Config) -> Result<()
         return Ok(());
     }
 
-    if !yes && io::stdin().is_terminal() {
+    if !yes && interactive::is_interactive() {
         let apply = Confirm::new()
             .with_prompt("Apply these changes?")
             .default(false)
@@ -492,38 +725,44 @@ async fn handle_chat(args: ChatArgs, config: &config::Config) -> Result<()> {
                 endpoint,
                 system_prompt: _,
                 timeout,
+                seed,
+                extra,
+                system_prompt_file,
             },
         directory,
+        no_autocontext,
+        preview,
     } = args;
 
     let provider_kind = provider
         .or_else(|| {
             std::env::var("ZARZ_PROVIDER")
                 .ok()
-                .and_then(|v| match v.to_ascii_lowercase().as_str() {
-                    "anthropic" => Some(Provider::Anthropic),
-                    "openai" => Some(Provider::OpenAi),
-                    "glm" => Some(Provider::Glm),
-                    _ => None,
-                })
+                .and_then(|v| Provider::from_str(&v))
         })
         .or_else(|| config.get_default_provider())
         .ok_or_else(|| anyhow!("No provider configured. Please run 'zarz config' to set up API keys."))?;
 
-    let model = resolve_model(model, &provider_kind)?;
+    let (model, thinking_budget) = resolve_model(model, &provider_kind, config)?;
     let working_dir = directory
         .or_else(|| env::current_dir().ok())
         .context("Failed to determine working directory")?;
+    let project_prompt = load_project_prompt(system_prompt_file.as_deref(), Some(&working_dir))?;
 
     // Get API key from config based on provider
     let api_key = match provider_kind {
         Provider::Anthropic => config.get_anthropic_key(),
         Provider::OpenAi => config.get_openai_key(),
         Provider::Glm => config.get_glm_key(),
+        Provider::OpenAiCompatible => config.get_openai_compatible_key(),
     };
 
     let provider_client = ProviderClient::new(provider_kind.clone(), api_key, endpoint.clone(), timeout)?;
 
+    if config::offline_mode() {
+        report_offline_mode(config);
+    }
+
     // Initialize MCP manager and load configured servers
     let mcp_manager = std::sync::Arc::new(mcp::McpManager::new());
     if let Err(e) = mcp_manager.load_from_config().await {
@@ -536,7 +775,17 @@ async fn handle_chat(args: ChatArgs, config: &config::Config) -> Result<()> {
     } else {
         None
     };
-
+    // Servers added with /server during the session live in the same manager
+    shutdown::install_sigterm_handler(Some(mcp_manager.clone()));
+    interrupt::install_handler();
+
+    let extra_overrides = parse_extra_params(extra.as_deref())?;
+    warn_managed_extra_params(
+        config
+            .extra_params_for(&provider_kind, extra_overrides.as_ref())
+            .as_ref(),
+    );
+    let max_tokens = resolve_max_tokens(config, &model, &provider_kind);
     let mut repl = Repl::new(
         working_dir,
         provider_client,
@@ -544,26 +793,251 @@ async fn handle_chat(args: ChatArgs, config: &config::Config) -> Result<()> {
         endpoint,
         timeout,
         model,
-        resolve_max_tokens(),
+        max_tokens,
         resolve_temperature(),
         mcp_manager_opt,
         config.clone(),
-    );
+    )
+    .with_seed(resolve_seed(seed))
+    .with_mcp_host(mcp_manager.clone())
+    .with_thinking_budget(thinking_budget)
+    .with_project_prompt(project_prompt)
+    .with_autocontext(!no_autocontext)
+    .with_preview(preview)
+    .with_extra_params(extra_overrides);
 
     let result = repl.run().await;
 
-    // Cleanup: stop all MCP servers
-    if has_mcp_servers {
-        if let Err(e) = mcp_manager.stop_all().await {
-            eprintln!("Warning: Failed to stop MCP servers: {}", e);
-        }
+    // Cleanup: stop all MCP servers, including any added with /server
+    if mcp_manager.has_servers().await
+        && let Err(e) = mcp_manager.stop_all().await
+    {
+        eprintln!("Warning: Failed to stop MCP servers: {}", e);
     }
 
     result
 }
 
+/// List what `--offline` switched off for this session, so a missing tool or server
+/// isn't a surprise.
+fn report_offline_mode(config: &config::Config) {
+    let mut disabled = vec!["update check".to_string()];
+    if config.web_fetch || repl::env_flag("ZARZ_WEB_FETCH") {
+        disabled.push("fetch_url tool".to_string());
+    }
+    if let Ok(mcp_config) = McpConfig::load() {
+        let mut remote: Vec<&String> = mcp_config
+            .mcp_servers
+            .iter()
+            .filter(|(_, server)| server.is_remote())
+            .map(|(name, _)| name)
+            .collect();
+        remote.sort();
+        if !remote.is_empty() {
+            disabled.push(format!(
+                "remote MCP server{} {}",
+                if remote.len() == 1 { "" } else { "s" },
+                remote.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+    eprintln!(
+        "{}",
+        format!(
+            "Offline mode: disabled {}. Only the model provider will be contacted.",
+            disabled.join(", ")
+        )
+        .dark_grey()
+    );
+}
+
+/// The project prompt for a command run in `working_dir` (the current directory when
+/// `None`).
+fn load_project_prompt(
+    explicit: Option<&Path>,
+    working_dir: Option<&Path>,
+) -> Result<Option<ProjectPrompt>> {
+    let working_dir = match working_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => env::current_dir().context("Failed to determine working directory")?,
+    };
+    ProjectPrompt::load(explicit, &working_dir)
+}
+
+// Output budget for `zarz ping`; the smallest value every provider accepts (the
+// OpenAI Responses API rejects anything below 16).
+const PING_MAX_TOKENS: u32 = 16;
+
+/// Send a trivial completion to each configured provider and report how it went.
+async fn handle_ping(args: PingArgs) -> Result<()> {
+    let PingArgs { provider, timeout } = args;
+    let mut config = config::Config::load()?;
+
+    if config.openai_oauth_tokens.is_some() && provider.as_ref().is_none_or(|p| *p == Provider::OpenAi) {
+        match auth::ensure_openai_oauth_tokens_fresh(&mut config).await {
+            Ok(true) => {
+                config.save()?;
+                println!("{}", "✓ ChatGPT OAuth token refreshed".with(Color::Green));
+            }
+            Ok(false) => println!("{}", "✓ ChatGPT OAuth token is fresh".with(Color::Green)),
+            Err(err) => println!(
+                "{}",
+                format!("✗ ChatGPT OAuth token could not be refreshed: {:#}", err).with(Color::Red)
+            ),
+        }
+    }
+    config.apply_to_env();
+
+    let providers: Vec<Provider> = match provider {
+        Some(provider) => vec![provider],
+        None => [Provider::Anthropic, Provider::OpenAi, Provider::Glm, Provider::OpenAiCompatible]
+            .into_iter()
+            .filter(|provider| match provider {
+                Provider::Anthropic => config.get_anthropic_key().is_some(),
+                Provider::OpenAi => config.get_openai_key().is_some(),
+                Provider::Glm => config.get_glm_key().is_some(),
+                Provider::OpenAiCompatible => config.uses_openai_compatible(),
+            })
+            .collect(),
+    };
+    if providers.is_empty() {
+        bail!("No providers are configured. Run 'zarz config' to set up API keys.");
+    }
+
+    let results = join_all(
+        providers
+            .iter()
+            .map(|provider| ping_provider(provider.clone(), &config, timeout)),
+    )
+    .await;
+
+    let mut failures = 0;
+    for (provider, (model, result)) in providers.iter().zip(results) {
+        match result {
+            Ok(elapsed) => println!(
+                "{} {:<18} {:<28} {} ms",
+                "✓".with(Color::Green),
+                provider.as_str(),
+                model,
+                elapsed.as_millis()
+            ),
+            Err(err) => {
+                failures += 1;
+                println!(
+                    "{} {:<18} {:<28} {}",
+                    "✗".with(Color::Red),
+                    provider.as_str(),
+                    model,
+                    format!("{:#}", err).with(Color::Red)
+                );
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} of {} provider(s) failed", failures, providers.len());
+    }
+    Ok(())
+}
+
+/// The model pinged and how long the round trip took.
+async fn ping_provider(
+    provider: Provider,
+    config: &config::Config,
+    timeout: Option<u64>,
+) -> (String, Result<std::time::Duration>) {
+    let model = match resolve_model(None, &provider, config) {
+        Ok((model, _)) => model,
+        Err(err) => return ("-".to_string(), Err(err)),
+    };
+    let api_key = match provider {
+        Provider::Anthropic => config.get_anthropic_key(),
+        Provider::OpenAi => config.get_openai_key(),
+        Provider::Glm => config.get_glm_key(),
+        Provider::OpenAiCompatible => config.get_openai_compatible_key(),
+    };
+    let client = match ProviderClient::new(provider, api_key, None, timeout) {
+        Ok(client) => client,
+        Err(err) => return (model, Err(err)),
+    };
+
+    let request = CompletionRequest {
+        model: model.clone(),
+        system_prompt: None,
+        user_prompt: "ping".to_string(),
+        max_output_tokens: PING_MAX_TOKENS,
+        temperature: 0.0,
+        messages: None,
+        tools: None,
+        reasoning_effort: None,
+        seed: None,
+        extra_params: None,
+        thinking_budget: None,
+        glm_thinking: None,
+        stream_tokens: None,
+    };
+    let started = std::time::Instant::now();
+    let result = client.complete(&request).await.map(|_| started.elapsed());
+    (model, result)
+}
+
 async fn handle_config(args: ConfigArgs) -> Result<()> {
-    let ConfigArgs { reset, show, login_chatgpt } = args;
+    let ConfigArgs { reset, show, login_chatgpt, encrypt_keys, list_profiles } = args;
+
+    if list_profiles {
+        let config = config::Config::load()?;
+        let active = config::active_profile();
+        if config.profiles.is_empty() {
+            println!("No profiles configured.");
+            println!(
+                "Add a [profiles.<name>] table to {} with its own anthropic_api_key, openai_api_key, glm_api_key, provider or model.",
+                config::Config::config_path()?.display()
+            );
+            return Ok(());
+        }
+        for (name, profile) in &config.profiles {
+            let marker = if active.as_deref() == Some(name.as_str()) { "●" } else { " " };
+            let mut keys = Vec::new();
+            if profile.anthropic_api_key.is_some() {
+                keys.push("anthropic");
+            }
+            if profile.openai_api_key.is_some() {
+                keys.push("openai");
+            }
+            if profile.glm_api_key.is_some() {
+                keys.push("glm");
+            }
+            let mut details = vec![if keys.is_empty() {
+                "no keys".to_string()
+            } else {
+                format!("keys: {}", keys.join(", "))
+            }];
+            if let Some(provider) = &profile.provider {
+                details.push(format!("provider: {}", provider));
+            }
+            if let Some(model) = &profile.model {
+                details.push(format!("model: {}", model));
+            }
+            println!("{} {:<16} {}", marker, name, details.join(" · ").with(Color::DarkGrey));
+        }
+        if active.is_none() {
+            println!();
+            println!("Select one with --profile <name> or ZARZ_PROFILE=<name>.");
+        }
+        return Ok(());
+    }
+
+    if encrypt_keys {
+        let mut config = config::Config::load()?;
+        config.encrypt_keys = true;
+        config.save()?;
+        println!("{}", "✓ Stored API keys are now encrypted".with(Color::Green).bold());
+        println!(
+            "  → Unlock with the passphrase, or set {} for non-interactive use",
+            "ZARZ_CONFIG_PASSPHRASE".with(Color::Yellow)
+        );
+        return Ok(());
+    }
 
     if login_chatgpt {
         let mut config = config::Config::load().unwrap_or_else(|_| config::Config::default());
@@ -601,6 +1075,9 @@ async fn handle_config(args: ConfigArgs) -> Result<()> {
         let config_path = config::Config::config_path()?;
 
         println!("Configuration file: {}", config_path.display());
+        if let Some(profile) = config::active_profile() {
+            println!("Active profile: {}", profile);
+        }
         println!();
 
         if config.anthropic_api_key.is_some() {
@@ -621,6 +1098,12 @@ async fn handle_config(args: ConfigArgs) -> Result<()> {
             println!("✗ OpenAI ChatGPT OAuth: not configured");
         }
 
+        if config.encrypt_keys {
+            println!("Key storage: encrypted");
+        } else {
+            println!("Key storage: plaintext (enable with `zarz config --encrypt-keys`)");
+        }
+
         if let Some(account) = &config.openai_chatgpt_account_id {
             println!("ChatGPT account ID: {}", account);
         } else {
@@ -647,6 +1130,12 @@ async fn handle_config(args: ConfigArgs) -> Result<()> {
             Some(ReasoningEffort::High) => println!("OpenAI reasoning effort: high"),
         }
 
+        match config.get_glm_thinking() {
+            None => println!("GLM thinking: Auto (model default)"),
+            Some(true) => println!("GLM thinking: enabled"),
+            Some(false) => println!("GLM thinking: disabled"),
+        }
+
         println!();
         println!("Run 'zarz config --reset' to reconfigure your API keys");
         println!("Run 'zarz config --login-chatgpt' to fetch a key via ChatGPT OAuth.");
@@ -666,6 +1155,73 @@ async fn handle_config(args: ConfigArgs) -> Result<()> {
     Ok(())
 }
 
+fn handle_sessions(args: SessionsArgs) -> Result<()> {
+    match args.command.unwrap_or(SessionsCommands::List(args.list)) {
+        SessionsCommands::List(list) => list_sessions(&list)?,
+        SessionsCommands::Rm { all: true, yes, .. } => {
+            let count = ConversationStore::list_summaries()?.len();
+            if count == 0 {
+                println!("No saved sessions.");
+                return Ok(());
+            }
+            if !yes {
+                interactive::require_interactive("Confirming the deletion", "Pass --yes to delete without asking.")?;
+                let confirmed = Confirm::new()
+                    .with_prompt(format!("Delete all {} saved sessions?", count))
+                    .default(false)
+                    .interact()?;
+                if !confirmed {
+                    println!("Aborted; no sessions were deleted.");
+                    return Ok(());
+                }
+            }
+            let deleted = ConversationStore::delete_all()?;
+            println!("Deleted {} saved session{}.", deleted, if deleted == 1 { "" } else { "s" });
+        }
+        SessionsCommands::Rm { id, .. } => {
+            let id = ConversationStore::resolve_id(id.as_deref().unwrap_or_default())?;
+            ConversationStore::delete(&id)?;
+            println!("Deleted session {}.", id);
+        }
+    }
+    Ok(())
+}
+
+/// `zarz sessions [list]`: one `format_session_line` per saved session, or JSON with `--json`.
+fn list_sessions(args: &SessionsListArgs) -> Result<()> {
+    let mut summaries = ConversationStore::list_summaries()?;
+    if let Some(limit) = args.limit {
+        summaries.truncate(limit);
+    }
+
+    if args.json {
+        let sessions: Vec<serde_json::Value> = summaries
+            .iter()
+            .map(|summary| {
+                serde_json::json!({
+                    "id": summary.id,
+                    "title": summary.title,
+                    "updated_at": summary.updated_at.to_rfc3339(),
+                    "provider": summary.provider,
+                    "model": summary.model,
+                    "message_count": summary.message_count,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
+    }
+
+    if summaries.is_empty() {
+        eprintln!("No saved sessions found.");
+        return Ok(());
+    }
+    for summary in &summaries {
+        println!("{}", repl::format_session_line(summary));
+    }
+    Ok(())
+}
+
 async fn handle_mcp(args: McpArgs) -> Result<()> {
     use std::collections::HashMap;
 
@@ -677,6 +1233,7 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
             env_vars,
             url,
             transport,
+            tool_timeout,
         } => {
             let mut config = McpConfig::load()?;
 
@@ -714,6 +1271,7 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
                     bail!("Invalid transport type: {}. Use: stdio, http, or sse", transport);
                 }
             };
+            let server_config = server_config.with_tool_timeout_secs(tool_timeout);
 
             config.add_server(name.clone(), server_config);
             config.save()?;
@@ -738,7 +1296,7 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
                 println!("\n  {}", name);
                 println!("    Type: {}", server_config.server_type());
                 match server_config {
-                    McpServerConfig::Stdio { command, args, env } => {
+                    McpServerConfig::Stdio { command, args, env, .. } => {
                         println!("    Command: {}", command);
                         if let Some(args) = args {
                             println!("    Args: {}", args.join(" "));
@@ -766,8 +1324,9 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
             if let Some(server_config) = config.get_server(&name) {
                 println!("MCP Server: {}", name);
                 println!("  Type: {}", server_config.server_type());
+                println!("  Tool timeout: {}s", server_config.tool_timeout().as_secs());
                 match server_config {
-                    McpServerConfig::Stdio { command, args, env } => {
+                    McpServerConfig::Stdio { command, args, env, .. } => {
                         println!("  Command: {}", command);
                         if let Some(args) = args {
                             println!("  Args: {}", args.join(" "));
@@ -781,7 +1340,7 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
                             }
                         }
                     }
-                    McpServerConfig::Http { url, headers } | McpServerConfig::Sse { url, headers } => {
+                    McpServerConfig::Http { url, headers, .. } | McpServerConfig::Sse { url, headers, .. } => {
                         println!("  URL: {}", url);
                         if let Some(headers) = headers {
                             if !headers.is_empty() {
@@ -811,10 +1370,84 @@ async fn handle_mcp(args: McpArgs) -> Result<()> {
             }
             Ok(())
         }
+
+        McpCommands::Import { file, overwrite } => {
+            let content = fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let document: serde_json::Value = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {} as JSON", file.display()))?;
+
+            let mut config = McpConfig::load()?;
+            let summary = config.import_servers(&document, overwrite)?;
+
+            if !summary.added.is_empty() || !summary.replaced.is_empty() {
+                config.save()?;
+            }
+
+            for name in &summary.added {
+                println!("[OK] Added MCP server: {}", name);
+            }
+            for name in &summary.replaced {
+                println!("[OK] Replaced MCP server: {}", name);
+            }
+            for name in &summary.skipped {
+                println!("Skipped '{}': already configured (use --overwrite to replace)", name);
+            }
+            for (name, reason) in &summary.invalid {
+                eprintln!("Warning: Skipped '{}': {}", name, reason);
+            }
+
+            println!(
+                "\nImported {} server(s): {} added, {} replaced, {} skipped, {} invalid",
+                summary.added.len() + summary.replaced.len(),
+                summary.added.len(),
+                summary.replaced.len(),
+                summary.skipped.len(),
+                summary.invalid.len()
+            );
+            if !summary.added.is_empty() || !summary.replaced.is_empty() {
+                println!("Configuration saved to: {}", McpConfig::config_path()?.display());
+            }
+            Ok(())
+        }
+        McpCommands::Export { file, include_secrets } => {
+            let config = McpConfig::load()?;
+            let document = config.export_servers(include_secrets);
+            let content = serde_json::to_string_pretty(&document)
+                .context("Failed to serialize MCP servers")?;
+
+            match file {
+                Some(path) => {
+                    fs::write(&path, format!("{}\n", content))
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    println!(
+                        "[OK] Exported {} MCP server(s) to {}",
+                        config.mcp_servers.len(),
+                        path.display()
+                    );
+                    if !include_secrets {
+                        println!("Env and header values were redacted (use --include-secrets to keep them)");
+                    }
+                }
+                None => println!("{}", content),
+            }
+            Ok(())
+        }
     }
 }
 
-fn resolve_model(model: Option<String>, provider: &Provider) -> Result<String> {
+/// The model to use, with a `-thinking` alias split into the Anthropic model id and
+/// its extended-thinking budget.
+fn resolve_model(
+    model: Option<String>,
+    provider: &Provider,
+    config: &config::Config,
+) -> Result<(String, Option<u32>)> {
+    let model = select_model(model, provider, config)?;
+    Ok(split_thinking_model(&model, provider))
+}
+
+fn select_model(model: Option<String>, provider: &Provider, config: &config::Config) -> Result<String> {
     if let Some(model) = model {
         return Ok(model);
     }
@@ -823,20 +1456,59 @@ fn resolve_model(model: Option<String>, provider: &Provider) -> Result<String> {
             return Ok(model);
         }
     }
+    if let Some(model) = config.model_for(provider) {
+        return Ok(model);
+    }
     // Use provider-specific default model
     let default_model = match provider {
         Provider::Anthropic => DEFAULT_MODEL_ANTHROPIC,
         Provider::OpenAi => DEFAULT_MODEL_OPENAI,
         Provider::Glm => DEFAULT_MODEL_GLM,
+        Provider::OpenAiCompatible => {
+            return Err(anyhow!(
+                "The openai-compatible provider has no default model. Pass --model or set ZARZ_MODEL to a model your server serves."
+            ));
+        }
     };
     Ok(default_model.to_string())
 }
 
-fn resolve_max_tokens() -> u32 {
+/// Crate version, the commit it was built from, and each provider's default model.
+/// Shown by `zarz --version` and `/version`.
+fn version_report() -> String {
+    let commit = match env!("ZARZ_GIT_COMMIT_DATE") {
+        "" => env!("ZARZ_GIT_COMMIT").to_string(),
+        date => format!("{} ({})", env!("ZARZ_GIT_COMMIT"), date),
+    };
+    let mut report = format!(
+        "zarz {}\ncommit:   {}\nplatform: {}-{}\ndefault models:",
+        env!("CARGO_PKG_VERSION"),
+        commit,
+        env::consts::OS,
+        env::consts::ARCH
+    );
+    for (provider, model) in [
+        (Provider::Anthropic, DEFAULT_MODEL_ANTHROPIC),
+        (Provider::OpenAi, DEFAULT_MODEL_OPENAI),
+        (Provider::Glm, DEFAULT_MODEL_GLM),
+    ] {
+        report.push_str(&format!("\n  {:<18} {}", provider.as_str(), model));
+    }
+    report.push_str(&format!("\n  {:<18} (none; pass --model)", Provider::OpenAiCompatible.as_str()));
+    report
+}
+
+fn resolve_max_tokens(config: &config::Config, model: &str, provider: &Provider) -> u32 {
+    env_max_output_tokens()
+        .or_else(|| config.max_output_tokens_for(model, provider))
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS)
+}
+
+/// `ZARZ_MAX_OUTPUT_TOKENS`, which wins over every `[max_output_tokens]` entry.
+fn env_max_output_tokens() -> Option<u32> {
     std::env::var("ZARZ_MAX_OUTPUT_TOKENS")
         .ok()
-        .and_then(|raw| raw.parse::<u32>().ok())
-        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS)
+        .and_then(|raw| raw.trim().parse::<u32>().ok())
 }
 
 fn resolve_temperature() -> f32 {
@@ -846,6 +1518,51 @@ fn resolve_temperature() -> f32 {
         .unwrap_or(0.3)
 }
 
+/// Parse `--extra`, which must be a JSON object.
+fn parse_extra_params(raw: Option<&str>) -> Result<Option<ExtraParams>> {
+    let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(None);
+    };
+    match serde_json::from_str::<serde_json::Value>(raw).context("--extra is not valid JSON")? {
+        serde_json::Value::Object(params) => Ok(Some(params)),
+        _ => Err(anyhow!("--extra must be a JSON object, e.g. '{{\"top_p\": 0.9}}'")),
+    }
+}
+
+fn warn_managed_extra_params(params: Option<&ExtraParams>) {
+    let Some(params) = params else {
+        return;
+    };
+    for key in params.keys() {
+        if providers::MANAGED_REQUEST_FIELDS.contains(&key.as_str()) {
+            eprintln!(
+                "Warning: extra parameter '{}' overrides a field ZarzCLI sets itself; this may break requests",
+                key
+            );
+        }
+    }
+}
+
+/// Merge `--extra` over the config's `[provider_extra]` for `provider`.
+fn resolve_extra_params(
+    config: &config::Config,
+    provider: &Provider,
+    raw: Option<&str>,
+) -> Result<Option<ExtraParams>> {
+    let overrides = parse_extra_params(raw)?;
+    let params = config.extra_params_for(provider, overrides.as_ref());
+    warn_managed_extra_params(params.as_ref());
+    Ok(params)
+}
+
+fn resolve_seed(cli_seed: Option<u64>) -> Option<u64> {
+    cli_seed.or_else(|| {
+        std::env::var("ZARZ_SEED")
+            .ok()
+            .and_then(|raw| raw.parse::<u64>().ok())
+    })
+}
+
 fn resolve_rewrite_temperature() -> f32 {
     std::env::var("ZARZ_REWRITE_TEMPERATURE")
         .ok()
@@ -880,25 +1597,206 @@ fn read_text_input(
     Err(anyhow!(err_message.to_string()))
 }
 
-fn build_context_section(files: &[PathBuf]) -> Result<String> {
+/// Files under `dir` that `ProjectIntelligence` ranks as relevant to `query`, most
+/// relevant first, until the byte budget is spent. Files already in `explicit` are
+/// skipped. The selection is reported on stderr so it can be checked.
+fn select_relevant_files(dir: &Path, query: &str, explicit: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        bail!("--context-dir {} is not a directory", dir.display());
+    }
+    let candidates = ProjectIntelligence::new(dir.to_path_buf())
+        .get_relevant_context(query)
+        .with_context(|| format!("Failed to search {} for relevant files", dir.display()))?;
+
+    let budget = context_max_bytes();
+    let mut used = 0usize;
+    let mut selected = Vec::new();
+    let mut skipped = Vec::new();
+    for path in candidates {
+        if explicit.iter().any(|file| same_file(file, &path)) {
+            continue;
+        }
+        let Ok(size) = fs::metadata(&path).map(|meta| meta.len() as usize) else {
+            continue;
+        };
+        if used + size > budget {
+            skipped.push((path, size));
+            continue;
+        }
+        used += size;
+        selected.push((path, size));
+    }
+
+    if selected.is_empty() {
+        eprintln!("{}", format!("No relevant files found in {}", dir.display()).dark_grey());
+    } else {
+        eprintln!(
+            "{}",
+            format!("Auto-selected context ({} KB):", used.div_ceil(1024)).dark_grey()
+        );
+        for (path, size) in &selected {
+            eprintln!("{}", format!("  {} ({} KB)", path.display(), size.div_ceil(1024)).dark_grey());
+        }
+    }
+    for (path, size) in &skipped {
+        eprintln!(
+            "{}",
+            format!(
+                "  skipped {} ({} KB would exceed the {} KB budget)",
+                path.display(),
+                size.div_ceil(1024),
+                budget / 1024
+            )
+            .dark_grey()
+        );
+    }
+
+    Ok(selected.into_iter().map(|(path, _)| path).collect())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn context_max_bytes() -> usize {
+    std::env::var("ZARZ_CONTEXT_MAX_BYTES")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_CONTEXT_MAX_BYTES)
+}
+
+fn context_budget_tokens(flag: Option<usize>) -> usize {
+    flag.or_else(|| {
+        std::env::var("ZARZ_CONTEXT_BUDGET")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+    })
+    .unwrap_or(DEFAULT_CONTEXT_BUDGET_TOKENS)
+}
+
+/// Wrap each file in a `<context>` block, keeping the total within the token budget.
+/// Files over their share are truncated, largest first, so small files stay whole;
+/// trimmed files are reported on stderr.
+fn build_context_section(files: &[PathBuf], budget: Option<usize>) -> Result<String> {
+    let contents = files
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path).with_context(|| format!("Failed to read context file {}", path.display()))
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    let budget_tokens = context_budget_tokens(budget);
+    let sizes: Vec<usize> = contents.iter().map(|content| content.chars().count()).collect();
+    let cap = match budget_tokens {
+        0 => None,
+        // About four characters per token, as session::estimate_tokens counts them
+        tokens => per_file_char_cap(&sizes, tokens.saturating_mul(4)),
+    };
+
     let mut sections = Vec::new();
-    for path in files {
-        let content =
-            fs::read_to_string(path)
-                .with_context(|| format!("Failed to read context file {}", path.display()))?;
-        sections.push(format!(
-            "<context path=\"{path}\">\n{content}\n</context>",
-            path = path.display(),
-            content = content
-        ));
+    let mut trimmed = Vec::new();
+    for ((path, content), size) in files.iter().zip(&contents).zip(&sizes) {
+        match cap {
+            Some(cap) if *size > cap => {
+                let mut kept: String = content.chars().take(cap).collect();
+                // Cut at a line boundary unless that would drop most of what fits
+                if let Some(end) = kept.rfind('\n')
+                    && end >= kept.len() / 2
+                {
+                    kept.truncate(end);
+                }
+                let kept_chars = kept.chars().count();
+                sections.push(format!(
+                    "<context path=\"{path}\" truncated=\"true\">\n{kept}\n... (truncated: first {kept_chars} of {size} characters)\n</context>",
+                    path = path.display(),
+                ));
+                trimmed.push((path, kept_chars, *size));
+            }
+            _ => sections.push(format!(
+                "<context path=\"{path}\">\n{content}\n</context>",
+                path = path.display(),
+                content = content
+            )),
+        }
+    }
+
+    if !trimmed.is_empty() {
+        eprintln!(
+            "{}",
+            format!("Context files exceed the {} token budget; truncated:", budget_tokens).dark_grey()
+        );
+        for (path, kept, size) in trimmed {
+            eprintln!(
+                "{}",
+                format!("  {} (kept {} of {} characters)", path.display(), kept, size).dark_grey()
+            );
+        }
     }
     Ok(sections.join("\n\n"))
 }
 
-fn build_rewrite_prompt(instructions: &str, files: &[(PathBuf, String)]) -> String {
+/// The most characters any one file may keep so `sizes` fit in `budget` together, or
+/// `None` when everything fits. Smaller files are kept whole and their unused share
+/// goes to the larger ones.
+fn per_file_char_cap(sizes: &[usize], budget: usize) -> Option<usize> {
+    if sizes.iter().sum::<usize>() <= budget {
+        return None;
+    }
+    let mut sorted = sizes.to_vec();
+    sorted.sort_unstable();
+    let mut remaining = budget;
+    for (idx, size) in sorted.iter().enumerate() {
+        let share = remaining / (sorted.len() - idx);
+        if *size > share {
+            return Some(share);
+        }
+        remaining -= size;
+    }
+    None
+}
+
+/// `(path, before, after)` for each target file after applying the `*** Update File`
+/// blocks in `response`. Files without a block are returned unchanged.
+fn patched_files(response: &str, files: &[(PathBuf, String)]) -> Result<Vec<(PathBuf, String, String)>> {
+    let updates = tools::parse_file_updates(response)?;
+    if updates.is_empty() {
+        bail!("Model response did not include any `*** Begin Patch` blocks to apply");
+    }
+
+    let mut diffs: Vec<(PathBuf, String, String)> = files
+        .iter()
+        .map(|(path, content)| (path.clone(), content.clone(), content.clone()))
+        .collect();
+    for update in updates {
+        let target = normalize_response_path(&update.path);
+        let Some((path, _, current)) = diffs
+            .iter_mut()
+            .find(|(path, _, _)| normalize_response_path(&normalize_path(path).to_string_lossy()) == target)
+        else {
+            bail!("Model patched {}, which is not one of the files given to rewrite", update.path);
+        };
+        let (patched, notes) = update
+            .apply(current)
+            .with_context(|| format!("Failed to apply the model's patch to {}", path.display()))?;
+        for note in notes {
+            eprintln!("{}", format!("{}: {}", path.display(), note).dark_grey());
+        }
+        *current = patched;
+    }
+    Ok(diffs)
+}
+
+fn build_rewrite_prompt(instructions: &str, files: &[(PathBuf, String)], patch: bool) -> String {
     let mut output = String::new();
     output.push_str("You will update the user's codebase according to the instructions.\n");
-    output.push_str("Return only the updated file contents as requested.\n\n");
+    if patch {
+        output.push_str("Return only patches for the files you change, as requested.\n\n");
+    } else {
+        output.push_str("Return only the updated file contents as requested.\n\n");
+    }
     output.push_str("## Instructions\n");
     output.push_str(instructions.trim());
     output.push_str("\n\n## Files\n");
@@ -1,15 +1,27 @@
 use anyhow::{anyhow, Context, Result};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use tokio::sync::Mutex;
 
 use super::config::McpServerConfig;
+use super::sse::SseConnection;
 use super::types::*;
 
+/// JSON-RPC id used when an SSE connection replays `initialize` after reconnecting;
+/// regular requests count up from 1.
+const REPLAY_INITIALIZE_ID: u64 = 0;
+
+/// Lines of stderr kept per STDIO server for `/mcp logs` (ZARZ_MCP_STDERR_LINES).
+const DEFAULT_STDERR_LINES: usize = 200;
+
+/// The most recent stderr lines of a STDIO server, oldest first.
+type StderrLog = Arc<std::sync::Mutex<VecDeque<String>>>;
+
 pub struct McpClient {
     #[allow(dead_code)]
     name: String,
@@ -17,6 +29,8 @@ pub struct McpClient {
     process: Option<Mutex<Child>>,
     stdin: Option<Mutex<ChildStdin>>,
     stdout: Option<Mutex<BufReader<ChildStdout>>>,
+    sse: Option<SseConnection>,
+    stderr_log: StderrLog,
     request_id: AtomicU64,
     initialized: bool,
     server_info: Option<ServerInfo>,
@@ -31,6 +45,8 @@ impl McpClient {
             process: None,
             stdin: None,
             stdout: None,
+            sse: None,
+            stderr_log: StderrLog::default(),
             request_id: AtomicU64::new(1),
             initialized: false,
             server_info: None,
@@ -40,7 +56,7 @@ impl McpClient {
 
     pub async fn start(&mut self) -> Result<()> {
         match &self.config {
-            McpServerConfig::Stdio { command, args, env } => {
+            McpServerConfig::Stdio { command, args, env, .. } => {
                 // On Windows, wrap in cmd /c for proper PATH resolution
                 let mut cmd = if cfg!(target_os = "windows") {
                     let mut win_cmd = Command::new("cmd");
@@ -64,7 +80,8 @@ impl McpClient {
 
                 cmd.stdin(Stdio::piped())
                     .stdout(Stdio::piped())
-                    .stderr(Stdio::inherit());
+                    // Captured rather than inherited so server logs can't garble the REPL
+                    .stderr(Stdio::piped());
 
                 let mut child = cmd.spawn()
                     .with_context(|| format!("Failed to start MCP server: {}", command))?;
@@ -73,6 +90,9 @@ impl McpClient {
                     .context("Failed to open stdin")?;
                 let stdout = child.stdout.take()
                     .context("Failed to open stdout")?;
+                if let Some(stderr) = child.stderr.take() {
+                    tokio::spawn(capture_stderr(stderr, self.stderr_log.clone()));
+                }
 
                 self.stdin = Some(Mutex::new(stdin));
                 self.stdout = Some(Mutex::new(BufReader::new(stdout)));
@@ -82,7 +102,14 @@ impl McpClient {
 
                 Ok(())
             }
-            _ => Err(anyhow!("Only STDIO servers are currently supported")),
+            McpServerConfig::Sse { url, headers, .. } => {
+                self.sse = Some(SseConnection::connect(&self.name, url, headers.as_ref()).await?);
+                self.initialize().await?;
+                Ok(())
+            }
+            McpServerConfig::Http { .. } => Err(anyhow!(
+                "Streamable HTTP MCP servers are not supported yet; use a STDIO or SSE server"
+            )),
         }
     }
 
@@ -100,7 +127,7 @@ impl McpClient {
             }
         });
 
-        let response = self.send_request("initialize", Some(params)).await?;
+        let response = self.send_request("initialize", Some(params.clone())).await?;
 
         let result: InitializeResult = serde_json::from_value(response)
             .context("Failed to parse initialize response")?;
@@ -111,6 +138,17 @@ impl McpClient {
 
         self.send_notification("notifications/initialized", None).await?;
 
+        if let Some(sse) = &self.sse {
+            let replay = json!({
+                "jsonrpc": "2.0",
+                "id": REPLAY_INITIALIZE_ID,
+                "method": "initialize",
+                "params": params,
+            });
+            sse.remember_initialize(replay, notification_message("notifications/initialized", None))
+                .await;
+        }
+
         Ok(())
     }
 
@@ -126,6 +164,11 @@ impl McpClient {
         Ok(result.tools)
     }
 
+    /// Limit on a single tool call, from the server's config.
+    pub fn tool_timeout(&self) -> std::time::Duration {
+        self.config.tool_timeout()
+    }
+
     #[allow(dead_code)]
     pub async fn call_tool(&self, name: String, arguments: Option<HashMap<String, Value>>) -> Result<CallToolResult> {
         if !self.initialized {
@@ -155,7 +198,6 @@ impl McpClient {
         Ok(result.resources)
     }
 
-    #[allow(dead_code)]
     pub async fn list_prompts(&self) -> Result<Vec<McpPrompt>> {
         if !self.initialized {
             return Err(anyhow!("MCP client not initialized"));
@@ -168,6 +210,21 @@ impl McpClient {
         Ok(result.prompts)
     }
 
+    pub async fn get_prompt(&self, name: String, arguments: Option<HashMap<String, String>>) -> Result<PromptGetResult> {
+        if !self.initialized {
+            return Err(anyhow!("MCP client not initialized"));
+        }
+
+        let params = GetPromptParams { name, arguments };
+        let params_value = serde_json::to_value(params)?;
+
+        let response = self.send_request("prompts/get", Some(params_value)).await?;
+        let result: PromptGetResult = serde_json::from_value(response)
+            .context("Failed to parse prompts/get response")?;
+
+        Ok(result)
+    }
+
     async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value> {
         let id = self.request_id.fetch_add(1, Ordering::SeqCst);
 
@@ -178,20 +235,27 @@ impl McpClient {
             params,
         };
 
-        let request_json = serde_json::to_string(&request)?;
-
-        if let Some(stdin) = &self.stdin {
-            let mut stdin = stdin.lock().await;
-            stdin.write_all(request_json.as_bytes()).await?;
-            stdin.write_all(b"\n").await?;
-            stdin.flush().await?;
-        } else {
-            return Err(anyhow!("STDIN not available"));
+        if let Some(sse) = &self.sse {
+            let response = sse.request(id, serde_json::to_value(&request)?).await?;
+            return response_result(response);
         }
 
+        let request_json = serde_json::to_string(&request)?;
+
         if let Some(stdout) = &self.stdout {
+            // Held across the write so concurrent callers can't read (and discard as
+            // mismatched) each other's responses
             let mut stdout = stdout.lock().await;
 
+            if let Some(stdin) = &self.stdin {
+                let mut stdin = stdin.lock().await;
+                stdin.write_all(request_json.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+                stdin.flush().await?;
+            } else {
+                return Err(anyhow!("STDIN not available"));
+            }
+
             loop {
                 let mut line = String::new();
                 let bytes_read = stdout.read_line(&mut line).await?;
@@ -227,15 +291,12 @@ impl McpClient {
                 let response: JsonRpcResponse = serde_json::from_value(value)
                     .with_context(|| format!("Failed to parse JSON-RPC response: {}", line.trim()))?;
 
-                if let Some(error) = response.error {
-                    return Err(anyhow!("MCP error: {} (code: {})", error.message, error.code));
+                // A late answer to a request that was abandoned (e.g. timed out)
+                if response.id != id {
+                    continue;
                 }
 
-                if let Some(result) = response.result {
-                    return Ok(result);
-                } else {
-                    return Err(anyhow!("No result in response"));
-                }
+                return response_result(response);
             }
         } else {
             Err(anyhow!("STDOUT not available"))
@@ -243,11 +304,11 @@ impl McpClient {
     }
 
     async fn send_notification(&self, method: &str, params: Option<Value>) -> Result<()> {
-        let notification = json!({
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params
-        });
+        let notification = notification_message(method, params);
+
+        if let Some(sse) = &self.sse {
+            return sse.notify(notification).await;
+        }
 
         let notification_json = serde_json::to_string(&notification)?;
 
@@ -261,11 +322,22 @@ impl McpClient {
         Ok(())
     }
 
+    /// False while an SSE connection is down and reconnecting; STDIO servers are
+    /// always considered connected.
+    pub fn is_connected(&self) -> bool {
+        self.sse.as_ref().map(SseConnection::is_connected).unwrap_or(true)
+    }
+
+    /// Recent stderr output of a STDIO server; always empty for remote servers.
+    pub fn stderr_lines(&self) -> Vec<String> {
+        let log = self.stderr_log.lock().unwrap_or_else(|e| e.into_inner());
+        log.iter().cloned().collect()
+    }
+
     pub fn server_info(&self) -> Option<&ServerInfo> {
         self.server_info.as_ref()
     }
 
-    #[allow(dead_code)]
     pub fn capabilities(&self) -> Option<&ServerCapabilities> {
         self.capabilities.as_ref()
     }
@@ -281,6 +353,9 @@ impl McpClient {
     }
 
     pub async fn stop(&mut self) -> Result<()> {
+        if let Some(sse) = &self.sse {
+            sse.close();
+        }
         if let Some(process) = &self.process {
             let mut process = process.lock().await;
             process.kill().await?;
@@ -299,3 +374,55 @@ impl Drop for McpClient {
         }
     }
 }
+
+/// Read a server's stderr until it exits, keeping only the newest lines.
+async fn capture_stderr(stderr: impl AsyncRead + Unpin, log: StderrLog) {
+    let limit = std::env::var("ZARZ_MCP_STDERR_LINES")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_STDERR_LINES);
+    let mut reader = BufReader::new(stderr);
+    let mut buf = Vec::new();
+    // Bytes, not lines: a line that isn't valid UTF-8 must not stop the capture
+    while let Ok(read) = reader.read_until(b'\n', &mut buf).await {
+        if read == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_string();
+        buf.clear();
+        let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+        log.push_back(line);
+        while log.len() > limit {
+            log.pop_front();
+        }
+    }
+}
+
+fn notification_message(method: &str, params: Option<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params
+    })
+}
+
+fn response_result(response: JsonRpcResponse) -> Result<Value> {
+    if let Some(error) = response.error {
+        return Err(anyhow!("MCP error: {} (code: {})", error.message, error.code));
+    }
+
+    response.result.ok_or_else(|| anyhow!("No result in response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stderr_capture_survives_invalid_utf8() {
+        let log = StderrLog::default();
+        capture_stderr(&b"starting\r\nbad \xff\xfe bytes\nstill running"[..], log.clone()).await;
+        let lines: Vec<String> = log.lock().unwrap().iter().cloned().collect();
+        assert_eq!(lines, ["starting", "bad \u{fffd}\u{fffd} bytes", "still running"]);
+    }
+}
@@ -1,8 +1,17 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// Placeholder written in place of env and header values by `mcp export`.
+pub const REDACTED_SECRET: &str = "<redacted>";
+
+/// How long a `tools/call` may run when the server sets no `tool_timeout_secs`.
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -13,19 +22,37 @@ pub enum McpServerConfig {
         args: Option<Vec<String>>,
         #[serde(skip_serializing_if = "Option::is_none")]
         env: Option<HashMap<String, String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_timeout_secs: Option<u64>,
     },
-    Http {
+    // Listed before `Http` so the untagged match picks it whenever `"type": "sse"` is
+    // present; otherwise both remote shapes look identical on disk.
+    Sse {
+        #[serde(rename = "type")]
+        transport: SseTransport,
         url: String,
         #[serde(skip_serializing_if = "Option::is_none")]
         headers: Option<HashMap<String, String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_timeout_secs: Option<u64>,
     },
-    Sse {
+    Http {
         url: String,
         #[serde(skip_serializing_if = "Option::is_none")]
         headers: Option<HashMap<String, String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_timeout_secs: Option<u64>,
     },
 }
 
+/// Marker serialized as `"type": "sse"` so SSE servers survive a save/load cycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SseTransport {
+    #[default]
+    Sse,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct McpConfig {
     #[serde(rename = "mcpServers")]
@@ -85,6 +112,52 @@ impl McpConfig {
         self.mcp_servers.get(name)
     }
 
+    /// Merge servers from a standard `{"mcpServers": {...}}` document, as written by
+    /// Claude Desktop, Cursor and other MCP clients. Existing names are skipped unless
+    /// `overwrite` is set.
+    pub fn import_servers(&mut self, document: &Value, overwrite: bool) -> Result<McpImportSummary> {
+        let servers = document
+            .get("mcpServers")
+            .or_else(|| document.get("servers"))
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| anyhow!("No \"mcpServers\" object found in file"))?;
+
+        let mut summary = McpImportSummary::default();
+        for (name, entry) in servers {
+            let server = match McpServerConfig::from_portable_json(entry) {
+                Ok(server) => server,
+                Err(err) => {
+                    summary.invalid.push((name.clone(), format!("{:#}", err)));
+                    continue;
+                }
+            };
+
+            if self.mcp_servers.contains_key(name) {
+                if !overwrite {
+                    summary.skipped.push(name.clone());
+                    continue;
+                }
+                summary.replaced.push(name.clone());
+            } else {
+                summary.added.push(name.clone());
+            }
+            self.mcp_servers.insert(name.clone(), server);
+        }
+
+        Ok(summary)
+    }
+
+    /// Serialize all servers into the portable `{"mcpServers": {...}}` format read by
+    /// `import_servers`. Env and header values are redacted unless `include_secrets` is set.
+    pub fn export_servers(&self, include_secrets: bool) -> Value {
+        let servers: serde_json::Map<String, Value> = self
+            .mcp_servers
+            .iter()
+            .map(|(name, server)| (name.clone(), server.to_portable_json(include_secrets)))
+            .collect();
+        serde_json::json!({ "mcpServers": servers })
+    }
+
     #[allow(dead_code)]
     pub fn list_servers(&self) -> Vec<String> {
         self.mcp_servers.keys().cloned().collect()
@@ -96,17 +169,153 @@ impl McpConfig {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct McpImportSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub skipped: Vec<String>,
+    pub invalid: Vec<(String, String)>,
+}
+
 impl McpServerConfig {
     pub fn stdio(command: String, args: Option<Vec<String>>, env: Option<HashMap<String, String>>) -> Self {
-        McpServerConfig::Stdio { command, args, env }
+        McpServerConfig::Stdio { command, args, env, tool_timeout_secs: None }
     }
 
     pub fn http(url: String, headers: Option<HashMap<String, String>>) -> Self {
-        McpServerConfig::Http { url, headers }
+        McpServerConfig::Http { url, headers, tool_timeout_secs: None }
     }
 
     pub fn sse(url: String, headers: Option<HashMap<String, String>>) -> Self {
-        McpServerConfig::Sse { url, headers }
+        McpServerConfig::Sse {
+            transport: SseTransport::Sse,
+            url,
+            headers,
+            tool_timeout_secs: None,
+        }
+    }
+
+    /// HTTP and SSE servers, which `--offline` refuses to start.
+    pub fn is_remote(&self) -> bool {
+        !matches!(self, McpServerConfig::Stdio { .. })
+    }
+
+    pub fn with_tool_timeout_secs(mut self, secs: Option<u64>) -> Self {
+        match &mut self {
+            McpServerConfig::Stdio { tool_timeout_secs, .. }
+            | McpServerConfig::Http { tool_timeout_secs, .. }
+            | McpServerConfig::Sse { tool_timeout_secs, .. } => *tool_timeout_secs = secs,
+        }
+        self
+    }
+
+    fn tool_timeout_secs(&self) -> Option<u64> {
+        match self {
+            McpServerConfig::Stdio { tool_timeout_secs, .. }
+            | McpServerConfig::Http { tool_timeout_secs, .. }
+            | McpServerConfig::Sse { tool_timeout_secs, .. } => *tool_timeout_secs,
+        }
+    }
+
+    /// Limit on a single tool call (`tool_timeout_secs`, default 60s).
+    pub fn tool_timeout(&self) -> Duration {
+        Duration::from_secs(self.tool_timeout_secs().unwrap_or(DEFAULT_TOOL_TIMEOUT_SECS))
+    }
+
+    /// Parse one server entry from another client's config. Remote servers are told apart
+    /// by an explicit `type`/`transport` field, falling back to an `/sse` URL suffix.
+    pub fn from_portable_json(entry: &Value) -> Result<Self> {
+        let string_map = |key: &str| -> Option<HashMap<String, String>> {
+            let map: HashMap<String, String> = entry
+                .get(key)?
+                .as_object()?
+                .iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect();
+            (!map.is_empty()).then_some(map)
+        };
+
+        let tool_timeout_secs = entry.get("tool_timeout_secs").and_then(|v| v.as_u64());
+
+        if let Some(command) = entry.get("command").and_then(|v| v.as_str()) {
+            let args: Option<Vec<String>> = entry
+                .get("args")
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str().map(str::to_string))
+                        .collect()
+                });
+            return Ok(Self::stdio(command.to_string(), args, string_map("env"))
+                .with_tool_timeout_secs(tool_timeout_secs));
+        }
+
+        if let Some(url) = entry.get("url").and_then(|v| v.as_str()) {
+            let transport = entry
+                .get("type")
+                .or_else(|| entry.get("transport"))
+                .and_then(|v| v.as_str())
+                .map(|t| t.to_ascii_lowercase());
+            let is_sse = match transport.as_deref() {
+                Some("sse") => true,
+                Some(_) => false,
+                None => url.trim_end_matches('/').ends_with("/sse"),
+            };
+            let headers = string_map("headers");
+            let server = if is_sse {
+                Self::sse(url.to_string(), headers)
+            } else {
+                Self::http(url.to_string(), headers)
+            };
+            return Ok(server.with_tool_timeout_secs(tool_timeout_secs));
+        }
+
+        Err(anyhow!("entry has neither a \"command\" nor a \"url\""))
+    }
+
+    /// Inverse of `from_portable_json`. Remote servers carry an explicit `type` so HTTP
+    /// and SSE survive the round trip.
+    pub fn to_portable_json(&self, include_secrets: bool) -> Value {
+        let secrets = |map: &Option<HashMap<String, String>>| -> Option<Value> {
+            let map = map.as_ref()?;
+            let redacted: serde_json::Map<String, Value> = map
+                .iter()
+                .map(|(key, value)| {
+                    let value = if include_secrets {
+                        value.clone()
+                    } else {
+                        REDACTED_SECRET.to_string()
+                    };
+                    (key.clone(), Value::String(value))
+                })
+                .collect();
+            Some(Value::Object(redacted))
+        };
+
+        let mut entry = serde_json::Map::new();
+        match self {
+            McpServerConfig::Stdio { command, args, env, .. } => {
+                entry.insert("command".into(), Value::String(command.clone()));
+                if let Some(args) = args {
+                    entry.insert("args".into(), serde_json::json!(args));
+                }
+                if let Some(env) = secrets(env) {
+                    entry.insert("env".into(), env);
+                }
+            }
+            McpServerConfig::Http { url, headers, .. } | McpServerConfig::Sse { url, headers, .. } => {
+                entry.insert("type".into(), Value::String(self.server_type().to_string()));
+                entry.insert("url".into(), Value::String(url.clone()));
+                if let Some(headers) = secrets(headers) {
+                    entry.insert("headers".into(), headers);
+                }
+            }
+        }
+        if let Some(secs) = self.tool_timeout_secs() {
+            entry.insert("tool_timeout_secs".into(), Value::from(secs));
+        }
+        Value::Object(entry)
     }
 
     pub fn server_type(&self) -> &'static str {
@@ -117,3 +326,95 @@ impl McpServerConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn secrets(pairs: &[(&str, &str)]) -> Option<HashMap<String, String>> {
+        Some(pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+    }
+
+    fn round_trip(server: &McpServerConfig) -> McpServerConfig {
+        McpServerConfig::from_portable_json(&server.to_portable_json(true)).unwrap()
+    }
+
+    fn assert_same(left: &McpServerConfig, right: &McpServerConfig) {
+        assert_eq!(serde_json::to_value(left).unwrap(), serde_json::to_value(right).unwrap());
+    }
+
+    #[test]
+    fn stdio_round_trips_with_timeout() {
+        let server = McpServerConfig::stdio(
+            "npx".to_string(),
+            Some(vec!["-y".to_string(), "@modelcontextprotocol/server-filesystem".to_string()]),
+            secrets(&[("API_TOKEN", "secret-token")]),
+        )
+        .with_tool_timeout_secs(Some(120));
+
+        let restored = round_trip(&server);
+        assert_same(&server, &restored);
+        assert_eq!(restored.tool_timeout(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn http_round_trips_with_timeout() {
+        let server = McpServerConfig::http(
+            "https://mcp.example.com/mcp".to_string(),
+            secrets(&[("Authorization", "Bearer abc")]),
+        )
+        .with_tool_timeout_secs(Some(30));
+
+        let restored = round_trip(&server);
+        assert_eq!(restored.server_type(), "http");
+        assert_same(&server, &restored);
+    }
+
+    #[test]
+    fn sse_round_trips_without_an_sse_suffix() {
+        let server = McpServerConfig::sse(
+            "https://mcp.example.com/events".to_string(),
+            secrets(&[("Authorization", "Bearer abc")]),
+        )
+        .with_tool_timeout_secs(Some(45));
+
+        let restored = round_trip(&server);
+        assert_eq!(restored.server_type(), "sse");
+        assert_same(&server, &restored);
+    }
+
+    #[test]
+    fn missing_timeout_stays_unset() {
+        let server = McpServerConfig::http("https://mcp.example.com/mcp".to_string(), None);
+        let exported = server.to_portable_json(false);
+        assert!(exported.get("tool_timeout_secs").is_none());
+        assert_eq!(
+            round_trip(&server).tool_timeout(),
+            Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS)
+        );
+    }
+
+    #[test]
+    fn export_redacts_env_and_headers_unless_asked() {
+        let stdio = McpServerConfig::stdio("server".to_string(), None, secrets(&[("API_TOKEN", "secret")]));
+        let exported = stdio.to_portable_json(false);
+        assert_eq!(exported["env"], json!({"API_TOKEN": REDACTED_SECRET}));
+        assert_eq!(stdio.to_portable_json(true)["env"], json!({"API_TOKEN": "secret"}));
+
+        for remote in [
+            McpServerConfig::http("https://a.example/mcp".to_string(), secrets(&[("Authorization", "Bearer x")])),
+            McpServerConfig::sse("https://a.example/sse".to_string(), secrets(&[("Authorization", "Bearer x")])),
+        ] {
+            let exported = remote.to_portable_json(false);
+            assert_eq!(exported["headers"], json!({"Authorization": REDACTED_SECRET}));
+
+            // The placeholder survives an import, where `zarz doctor` flags it
+            let restored = McpServerConfig::from_portable_json(&exported).unwrap();
+            let (McpServerConfig::Http { headers, .. } | McpServerConfig::Sse { headers, .. }) = restored else {
+                panic!("expected a remote server");
+            };
+            assert_eq!(headers, secrets(&[("Authorization", REDACTED_SECRET)]));
+        }
+    }
+}
@@ -1,19 +1,60 @@
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use futures::future::join_all;
 use tokio::sync::RwLock;
 
 use super::client::McpClient;
 use super::config::{McpConfig, McpServerConfig};
-use super::types::{McpTool, McpResource, McpPrompt};
+use super::types::{McpTool, McpResource, McpPrompt, PromptGetResult};
+
+/// How long `get_all_tools` waits for one server (ZARZ_MCP_LIST_TIMEOUT).
+const DEFAULT_LIST_TIMEOUT_SECS: u64 = 10;
 
 pub struct McpManager {
     clients: RwLock<HashMap<String, McpClient>>,
+    health: Mutex<HashMap<String, ServerHealth>>,
+    /// Last tool list per server, reused while a remote server is reconnecting so its
+    /// tools stay visible (calls to them report the disconnect).
+    known_tools: Mutex<HashMap<String, Vec<McpTool>>>,
+}
+
+/// Tools per server from `get_all_tools`, plus the servers that timed out.
+#[derive(Debug, Default)]
+pub struct ToolListing {
+    pub tools: HashMap<String, Vec<McpTool>>,
+    pub timed_out: Vec<String>,
+}
+
+/// Per-server activity, shown by `/mcp` so failing servers stand out.
+#[derive(Debug, Clone)]
+pub struct ServerHealth {
+    pub started_at: Instant,
+    pub last_call_at: Option<Instant>,
+    pub call_count: u64,
+    pub error_count: u64,
+    pub last_error: Option<String>,
+}
+
+impl ServerHealth {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            last_call_at: None,
+            call_count: 0,
+            error_count: 0,
+            last_error: None,
+        }
+    }
 }
 
 impl McpManager {
     pub fn new() -> Self {
         Self {
             clients: RwLock::new(HashMap::new()),
+            health: Mutex::new(HashMap::new()),
+            known_tools: Mutex::new(HashMap::new()),
         }
     }
 
@@ -21,6 +62,10 @@ impl McpManager {
         let config = McpConfig::load()?;
 
         for (name, server_config) in config.mcp_servers {
+            // Reported once at startup rather than as a failure per server
+            if server_config.is_remote() && crate::config::offline_mode() {
+                continue;
+            }
             if let Err(e) = self.start_server(name.clone(), server_config).await {
                 eprintln!("Warning: Failed to start MCP server '{}': {}", name, e);
             }
@@ -29,21 +74,27 @@ impl McpManager {
         Ok(())
     }
 
+    /// Start and initialize a server. A server that fails to come up is not added.
     pub async fn start_server(&self, name: String, config: McpServerConfig) -> Result<()> {
+        if config.is_remote() && crate::config::offline_mode() {
+            return Err(anyhow!("remote MCP servers are disabled in offline mode"));
+        }
         let mut client = McpClient::new(name.clone(), config);
         client.start().await?;
 
         let mut clients = self.clients.write().await;
+        self.health_map().insert(name.clone(), ServerHealth::new());
         clients.insert(name, client);
 
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub async fn stop_server(&self, name: &str) -> Result<()> {
         let mut clients = self.clients.write().await;
 
         if let Some(mut client) = clients.remove(name) {
+            self.health_map().remove(name);
+            self.known_tools_map().remove(name);
             client.stop().await?;
             Ok(())
         } else {
@@ -56,22 +107,47 @@ impl McpManager {
         clients.keys().cloned().collect()
     }
 
-    pub async fn get_all_tools(&self) -> Result<HashMap<String, Vec<McpTool>>> {
+    /// List tools from every server concurrently. A server that errors or does not
+    /// answer within `ZARZ_MCP_LIST_TIMEOUT` seconds is left out of `tools` (timeouts are
+    /// named in `timed_out`) so it cannot hold up the others.
+    pub async fn get_all_tools(&self) -> Result<ToolListing> {
         let clients = self.clients.read().await;
-        let mut all_tools = HashMap::new();
+        let timeout = list_timeout();
 
-        for (name, client) in clients.iter() {
-            match client.list_tools().await {
-                Ok(tools) => {
-                    all_tools.insert(name.clone(), tools);
+        let results = join_all(clients.iter().map(|(name, client)| async move {
+            let result = tokio::time::timeout(timeout, client.list_tools()).await;
+            (name, client, result)
+        }))
+        .await;
+
+        let mut listing = ToolListing::default();
+        for (name, client, result) in results {
+            match result {
+                Ok(Ok(tools)) => {
+                    self.known_tools_map().insert(name.clone(), tools.clone());
+                    listing.tools.insert(name.clone(), tools);
                 }
-                Err(e) => {
+                Ok(Err(_)) if !client.is_connected() => {
+                    if let Some(tools) = self.known_tools_map().get(name) {
+                        listing.tools.insert(name.clone(), tools.clone());
+                    }
+                }
+                Ok(Err(e)) => {
                     eprintln!("Warning: Failed to get tools from '{}': {}", name, e);
                 }
+                Err(_) => {
+                    eprintln!(
+                        "Warning: MCP server '{}' did not list its tools within {}s",
+                        name,
+                        timeout.as_secs()
+                    );
+                    listing.timed_out.push(name.clone());
+                }
             }
         }
+        listing.timed_out.sort();
 
-        Ok(all_tools)
+        Ok(listing)
     }
 
     #[allow(dead_code)]
@@ -93,12 +169,20 @@ impl McpManager {
         Ok(all_resources)
     }
 
-    #[allow(dead_code)]
     pub async fn get_all_prompts(&self) -> Result<HashMap<String, Vec<McpPrompt>>> {
         let clients = self.clients.read().await;
         let mut all_prompts = HashMap::new();
 
         for (name, client) in clients.iter() {
+            // Servers that don't advertise prompts would just answer with "method not found"
+            let supports_prompts = client
+                .capabilities()
+                .map(|caps| caps.prompts.is_some())
+                .unwrap_or(false);
+            if !supports_prompts {
+                continue;
+            }
+
             match client.list_prompts().await {
                 Ok(prompts) => {
                     all_prompts.insert(name.clone(), prompts);
@@ -136,7 +220,87 @@ impl McpManager {
         let client = clients.get(server_name)
             .ok_or_else(|| anyhow!("Server '{}' not found", server_name))?;
 
-        client.call_tool(tool_name, arguments).await
+        // A hung tool must not freeze the turn; the timeout is reported as a tool error
+        // so the model sees it like any other failed call
+        let timeout = client.tool_timeout();
+        let call = client.call_tool(tool_name.clone(), arguments);
+        let result = match tokio::time::timeout(timeout, call).await {
+            Ok(result) => result,
+            Err(_) => Ok(super::types::CallToolResult {
+                content: vec![super::types::ToolContent::Text {
+                    text: format!(
+                        "MCP tool '{}' on server '{}' timed out after {}s (raise tool_timeout_secs in ~/.zarz/mcp.json if it needs longer)",
+                        tool_name,
+                        server_name,
+                        timeout.as_secs()
+                    ),
+                }],
+                is_error: Some(true),
+            }),
+        };
+        self.record_call(server_name, &result);
+        result
+    }
+
+    fn known_tools_map(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<McpTool>>> {
+        self.known_tools.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// False while a remote server's connection is down and being re-established.
+    pub async fn is_connected(&self, name: &str) -> bool {
+        let clients = self.clients.read().await;
+        clients.get(name).map(McpClient::is_connected).unwrap_or(false)
+    }
+
+    fn health_map(&self) -> std::sync::MutexGuard<'_, HashMap<String, ServerHealth>> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Count a tool call; transport failures and results flagged `isError` both count as errors.
+    fn record_call(&self, server_name: &str, result: &Result<super::types::CallToolResult>) {
+        let mut health = self.health_map();
+        let Some(entry) = health.get_mut(server_name) else {
+            return;
+        };
+        entry.call_count += 1;
+        entry.last_call_at = Some(Instant::now());
+
+        let error = match result {
+            Err(err) => Some(format!("{:#}", err)),
+            Ok(result) if result.is_error == Some(true) => Some(
+                result
+                    .content
+                    .iter()
+                    .find_map(|content| match content {
+                        super::types::ToolContent::Text { text } => Some(text.clone()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| "tool reported an error".to_string()),
+            ),
+            Ok(_) => None,
+        };
+        if let Some(error) = error {
+            entry.error_count += 1;
+            entry.last_error = Some(error);
+        }
+    }
+
+    pub fn server_health(&self, name: &str) -> Option<ServerHealth> {
+        self.health_map().get(name).cloned()
+    }
+
+    pub async fn get_prompt(
+        &self,
+        server_name: &str,
+        prompt_name: String,
+        arguments: Option<HashMap<String, String>>,
+    ) -> Result<PromptGetResult> {
+        let clients = self.clients.read().await;
+
+        let client = clients.get(server_name)
+            .ok_or_else(|| anyhow!("Server '{}' not found", server_name))?;
+
+        client.get_prompt(prompt_name, arguments).await
     }
 
     pub async fn get_server_info(&self, name: &str) -> Option<String> {
@@ -148,6 +312,12 @@ impl McpManager {
         })
     }
 
+    /// Recent stderr of a running server, or `None` if no such server is running.
+    pub async fn stderr_lines(&self, name: &str) -> Option<Vec<String>> {
+        let clients = self.clients.read().await;
+        clients.get(name).map(McpClient::stderr_lines)
+    }
+
     pub async fn has_servers(&self) -> bool {
         let clients = self.clients.read().await;
         !clients.is_empty()
@@ -156,6 +326,7 @@ impl McpManager {
     pub async fn stop_all(&self) -> Result<()> {
         let mut clients = self.clients.write().await;
 
+        self.health_map().clear();
         for (name, mut client) in clients.drain() {
             if let Err(e) = client.stop().await {
                 eprintln!("Warning: Failed to stop server '{}': {}", name, e);
@@ -171,3 +342,12 @@ impl Default for McpManager {
         Self::new()
     }
 }
+
+fn list_timeout() -> Duration {
+    let secs = std::env::var("ZARZ_MCP_LIST_TIMEOUT")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_LIST_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
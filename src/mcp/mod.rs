@@ -3,6 +3,7 @@ pub mod config;
 pub mod client;
 pub mod types;
 pub mod manager;
+pub mod sse;
 
 pub use config::{McpConfig, McpServerConfig};
 #[allow(unused_imports)]
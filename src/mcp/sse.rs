@@ -0,0 +1,345 @@
+use anyhow::{anyhow, Context, Result};
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use reqwest::{Client, Url};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, watch, Mutex};
+use tokio::task::JoinHandle;
+
+use super::types::JsonRpcResponse;
+
+/// How long `connect` waits for the server to announce its message endpoint.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// MCP over Server-Sent Events: a long-lived GET stream delivers responses, and
+/// requests are POSTed to the endpoint the server announces on that stream.
+///
+/// A background task keeps the stream open, reconnecting with exponential backoff
+/// (and `Last-Event-ID` when the server sent event ids). While it is down, requests
+/// fail fast with a "temporarily disconnected" error instead of hanging.
+pub struct SseConnection {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+struct Shared {
+    name: String,
+    url: Url,
+    http: Client,
+    /// Where to POST messages; `None` while disconnected.
+    endpoint: watch::Sender<Option<Url>>,
+    pending: std::sync::Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>,
+    last_event_id: Mutex<Option<String>>,
+    /// Set once the first connection has been initialized, so reconnects know to
+    /// repeat the handshake.
+    initialized: AtomicBool,
+    /// Sent again after every reconnect; servers usually start a fresh session.
+    initialize_request: Mutex<Option<(Value, Value)>>,
+}
+
+impl SseConnection {
+    pub async fn connect(
+        name: &str,
+        url: &str,
+        headers: Option<&HashMap<String, String>>,
+    ) -> Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("Invalid MCP SSE URL: {}", url))?;
+
+        let mut default_headers = HeaderMap::new();
+        for (key, value) in headers.into_iter().flatten() {
+            let name = HeaderName::from_bytes(key.as_bytes())
+                .with_context(|| format!("Invalid header name '{}'", key))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for header '{}'", key))?;
+            default_headers.insert(name, value);
+        }
+        let http = Client::builder()
+            .default_headers(default_headers)
+            .user_agent("zarz-cli/0.1")
+            .build()
+            .context("Failed to build HTTP client for MCP SSE")?;
+
+        let (endpoint_tx, mut endpoint_rx) = watch::channel(None);
+        let shared = Arc::new(Shared {
+            name: name.to_string(),
+            url,
+            http,
+            endpoint: endpoint_tx,
+            pending: std::sync::Mutex::new(HashMap::new()),
+            last_event_id: Mutex::new(None),
+            initialized: AtomicBool::new(false),
+            initialize_request: Mutex::new(None),
+        });
+
+        let task = tokio::spawn(run_stream(shared.clone()));
+
+        let announced = tokio::time::timeout(
+            CONNECT_TIMEOUT,
+            endpoint_rx.wait_for(|endpoint| endpoint.is_some()),
+        )
+        .await;
+        if !matches!(announced, Ok(Ok(_))) {
+            task.abort();
+            return Err(anyhow!(
+                "MCP server '{}' did not announce a message endpoint within {}s",
+                name,
+                CONNECT_TIMEOUT.as_secs()
+            ));
+        }
+
+        Ok(Self { shared, task })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.shared.endpoint.borrow().is_some()
+    }
+
+    /// Send a JSON-RPC request and wait for its response to arrive on the stream.
+    pub async fn request(&self, id: u64, message: Value) -> Result<JsonRpcResponse> {
+        self.shared.request(id, message).await
+    }
+
+    pub async fn notify(&self, message: Value) -> Result<()> {
+        self.shared.post(&message).await
+    }
+
+    /// Remember the initialize handshake so it can be replayed after a reconnect.
+    pub async fn remember_initialize(&self, request: Value, initialized_notification: Value) {
+        *self.shared.initialize_request.lock().await = Some((request, initialized_notification));
+        self.shared.initialized.store(true, Ordering::SeqCst);
+    }
+
+    pub fn close(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for SseConnection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Shared {
+    fn disconnected_error(&self) -> anyhow::Error {
+        anyhow!(
+            "MCP server '{}' is temporarily disconnected (reconnecting); try again shortly",
+            self.name
+        )
+    }
+
+    async fn post(&self, message: &Value) -> Result<()> {
+        let endpoint = self
+            .endpoint
+            .borrow()
+            .clone()
+            .ok_or_else(|| self.disconnected_error())?;
+        self.http
+            .post(endpoint)
+            .json(message)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send message to MCP server '{}'", self.name))?
+            .error_for_status()
+            .with_context(|| format!("MCP server '{}' rejected the message", self.name))?;
+        Ok(())
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, HashMap<u64, oneshot::Sender<JsonRpcResponse>>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn request(&self, id: u64, message: Value) -> Result<JsonRpcResponse> {
+        let (tx, rx) = oneshot::channel();
+        self.pending().insert(id, tx);
+        let _entry = PendingEntry { shared: self, id };
+        self.post(&message).await?;
+        // The sender is dropped when the stream disconnects, failing the wait
+        rx.await.map_err(|_| self.disconnected_error())
+    }
+
+    /// Route one SSE event: the endpoint announcement, or a JSON-RPC message.
+    async fn handle_event(&self, event: &str, data: &str) {
+        match event {
+            "endpoint" => match self.url.join(data.trim()) {
+                // The client sends the configured headers (often credentials) with every
+                // POST, so the endpoint must stay on the server's own origin
+                Ok(endpoint) if endpoint.origin() == self.url.origin() => {
+                    self.endpoint.send_replace(Some(endpoint));
+                }
+                Ok(endpoint) => eprintln!(
+                    "Warning: MCP server '{}' announced an endpoint on another origin ({}); ignoring it",
+                    self.name, endpoint
+                ),
+                Err(err) => eprintln!(
+                    "Warning: MCP server '{}' announced an invalid endpoint '{}': {}",
+                    self.name, data, err
+                ),
+            },
+            "message" | "" => {
+                let Ok(value) = serde_json::from_str::<Value>(data) else {
+                    return;
+                };
+                if value.get("id").is_none() {
+                    if let Some(msg) = value
+                        .get("params")
+                        .and_then(|p| p.get("data"))
+                        .and_then(|d| d.get("message"))
+                        .and_then(|m| m.as_str())
+                    {
+                        eprintln!("MCP notification: {}", msg);
+                    }
+                    return;
+                }
+                if let Ok(response) = serde_json::from_value::<JsonRpcResponse>(value)
+                    && let Some(sender) = self.pending().remove(&response.id)
+                {
+                    sender.send(response).ok();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Repeat the initialize handshake on a fresh connection.
+    async fn reinitialize(self: Arc<Self>) {
+        let Some((request, notification)) = self.initialize_request.lock().await.clone() else {
+            return;
+        };
+        let id = request.get("id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let result = match self.request(id, request).await {
+            Ok(_) => self.post(&notification).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => eprintln!("Reconnected to MCP server '{}'", self.name),
+            Err(err) => eprintln!(
+                "Warning: Reconnected to MCP server '{}' but re-initialization failed: {:#}",
+                self.name, err
+            ),
+        }
+    }
+}
+
+/// Drops a request's `pending` entry when the wait ends, including when the caller
+/// abandons it (a tool timeout drops the future) before the response arrives.
+struct PendingEntry<'a> {
+    shared: &'a Shared,
+    id: u64,
+}
+
+impl Drop for PendingEntry<'_> {
+    fn drop(&mut self) {
+        self.shared.pending().remove(&self.id);
+    }
+}
+
+/// Keep the event stream open for the life of the connection.
+async fn run_stream(shared: Arc<Shared>) {
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let mut request = shared
+            .http
+            .get(shared.url.clone())
+            .header(ACCEPT, "text/event-stream");
+        if let Some(last_id) = shared.last_event_id.lock().await.clone() {
+            request = request.header("Last-Event-ID", last_id);
+        }
+
+        let outcome = match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => {
+                let reconnecting = shared.initialized.load(Ordering::SeqCst);
+                let result = read_events(&shared, response, reconnecting, &mut backoff).await;
+                result.map(|()| "stream closed".to_string())
+            }
+            Err(err) => Err(err.into()),
+        };
+
+        // Anything still waiting on this connection will never get an answer
+        shared.endpoint.send_replace(None);
+        shared.pending().clear();
+
+        let reason = match outcome {
+            Ok(reason) => reason,
+            Err(err) => format!("{:#}", err),
+        };
+        eprintln!(
+            "MCP server '{}' disconnected ({}); reconnecting in {}s...",
+            shared.name,
+            reason,
+            backoff.as_secs()
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Parse `text/event-stream` framing until the stream ends.
+async fn read_events(
+    shared: &Arc<Shared>,
+    response: reqwest::Response,
+    reconnecting: bool,
+    backoff: &mut Duration,
+) -> Result<()> {
+    let mut stream = response.bytes_stream();
+    // Raw bytes, decoded one complete line at a time so a multibyte character split
+    // across chunks isn't mangled
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut event = String::new();
+    let mut data = String::new();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.context("MCP event stream failed")?;
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(newline) = buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line[..newline]);
+            let line = line.trim_end_matches('\r');
+
+            if line.is_empty() {
+                if !data.is_empty() || !event.is_empty() {
+                    let was_connected = shared.endpoint.borrow().is_some();
+                    shared.handle_event(&event, data.trim_end_matches('\n')).await;
+                    if !was_connected && shared.endpoint.borrow().is_some() {
+                        *backoff = INITIAL_BACKOFF;
+                        if reconnecting {
+                            tokio::spawn(shared.clone().reinitialize());
+                        }
+                    }
+                }
+                event.clear();
+                data.clear();
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => event = value.to_string(),
+                "data" => {
+                    data.push_str(value);
+                    data.push('\n');
+                }
+                "id" => *shared.last_event_id.lock().await = Some(value.to_string()),
+                "retry" => {
+                    if let Ok(ms) = value.trim().parse::<u64>() {
+                        *backoff = Duration::from_millis(ms).clamp(INITIAL_BACKOFF, MAX_BACKOFF);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
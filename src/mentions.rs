@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::tools::{exploration_walker, resolve_safe_path};
+
+/// Total bytes `@mentions` may attach to one message (ZARZ_MENTION_MAX_BYTES).
+const DEFAULT_MENTION_MAX_BYTES: usize = 100_000;
+
+/// Entries shown for an `@dir/` mention before the listing is cut off.
+const DIR_LISTING_LIMIT: usize = 200;
+const DIR_LISTING_DEPTH: usize = 2;
+
+/// Sentence punctuation trimmed from the end of a mention ("see @src/main.rs.").
+const TRAILING_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?', ')', '"', '\''];
+
+/// Opens the context built by `to_context`.
+const CONTEXT_HEADER: &str = "Files the user referenced with @mentions in their latest message:";
+
+/// Opens the note `history_note` leaves in the conversation in place of the contents.
+pub const HISTORY_NOTE_PREFIX: &str = "Attached with @mentions";
+
+/// Files and directory listings attached to a single user message via `@path`.
+#[derive(Debug, Default)]
+pub struct MentionAttachments {
+    /// `<file>` / `<directory>` sections ready to be added to the conversation.
+    pub sections: Vec<String>,
+    /// One line per mention, for telling the user what was attached or skipped.
+    pub notes: Vec<String>,
+    /// The mentions that made it into `sections`.
+    pub paths: Vec<String>,
+}
+
+impl MentionAttachments {
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// The attachments as a single block of context for the turn's prompt.
+    pub fn to_context(&self) -> String {
+        format!("{}\n\n{}", CONTEXT_HEADER, self.sections.join("\n\n"))
+    }
+
+    /// What stays in the conversation once the turn is over: the paths, not the contents.
+    pub fn history_note(&self) -> String {
+        format!(
+            "{} (contents were sent with that message only): {}",
+            HISTORY_NOTE_PREFIX,
+            self.paths.join(", ")
+        )
+    }
+}
+
+/// Where an `@path` token points.
+enum MentionTarget {
+    Inside(PathBuf),
+    /// Exists, but is absolute, climbs out with `..`, or leaves through a symlink.
+    Outside,
+    Missing,
+}
+
+fn resolve_mention(mention: &str, working_dir: &Path, root: Option<&Path>) -> MentionTarget {
+    let candidate = working_dir.join(mention);
+    let Ok(canonical) = candidate.canonicalize() else {
+        return MentionTarget::Missing;
+    };
+    let inside = resolve_safe_path(working_dir, mention, "@mention").is_ok()
+        && root.is_some_and(|root| canonical.starts_with(root));
+    if inside {
+        MentionTarget::Inside(candidate)
+    } else {
+        MentionTarget::Outside
+    }
+}
+
+/// Find `@path` tokens that name an existing file or directory under `working_dir`
+/// and read them. Tokens that don't resolve (`@Override`, `@user`) are left alone;
+/// paths outside `working_dir` are skipped with a note.
+pub fn collect_mentions(input: &str, working_dir: &Path) -> MentionAttachments {
+    let mut attachments = MentionAttachments::default();
+    let mut remaining = max_bytes();
+    let mut seen = Vec::new();
+    let root = working_dir.canonicalize().ok();
+
+    for token in input.split_whitespace() {
+        let Some(raw) = token.strip_prefix('@') else {
+            continue;
+        };
+        let mention = raw.trim_end_matches(TRAILING_PUNCTUATION);
+        if mention.is_empty() || seen.iter().any(|s: &String| s == mention) {
+            continue;
+        }
+
+        let path = match resolve_mention(mention, working_dir, root.as_deref()) {
+            MentionTarget::Inside(path) => path,
+            MentionTarget::Outside => {
+                seen.push(mention.to_string());
+                attachments
+                    .notes
+                    .push(format!("Skipped @{} (outside the project directory)", mention));
+                continue;
+            }
+            MentionTarget::Missing => continue,
+        };
+        if path.is_dir() {
+            seen.push(mention.to_string());
+            let listing = directory_listing(&path, working_dir);
+            if listing.len() > remaining {
+                attachments
+                    .notes
+                    .push(format!("Skipped @{} (listing exceeds the attachment limit)", mention));
+                continue;
+            }
+            remaining -= listing.len();
+            attachments.sections.push(format!(
+                "<directory path=\"{}\">\n{}\n</directory>",
+                mention, listing
+            ));
+            attachments.paths.push(mention.to_string());
+            attachments.notes.push(format!("Attached listing of {}", mention));
+        } else if path.is_file() {
+            seen.push(mention.to_string());
+            // Check the size first so an oversized file is never read into memory
+            let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            if size > remaining as u64 {
+                attachments.notes.push(format!(
+                    "Skipped @{} ({} KB would exceed the {} KB attachment limit)",
+                    mention,
+                    size.div_ceil(1024),
+                    max_bytes() / 1024
+                ));
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path) else {
+                attachments
+                    .notes
+                    .push(format!("Skipped @{} (not a text file)", mention));
+                continue;
+            };
+            if content.len() > remaining {
+                attachments.notes.push(format!(
+                    "Skipped @{} ({} KB would exceed the {} KB attachment limit)",
+                    mention,
+                    content.len().div_ceil(1024),
+                    max_bytes() / 1024
+                ));
+                continue;
+            }
+            remaining -= content.len();
+            attachments.notes.push(format!(
+                "Attached {} ({} KB)",
+                mention,
+                content.len().div_ceil(1024)
+            ));
+            attachments
+                .sections
+                .push(format!("<file path=\"{}\">\n{}\n</file>", mention, content));
+            attachments.paths.push(mention.to_string());
+        }
+    }
+
+    attachments
+}
+
+/// Indented tree of `dir`, relative to `working_dir`, honoring `.gitignore`.
+pub fn directory_listing(dir: &Path, working_dir: &Path) -> String {
+    let mut walker = exploration_walker(dir, false);
+    walker.max_depth(Some(DIR_LISTING_DEPTH));
+
+    let mut lines = Vec::new();
+    let mut total = 0usize;
+    for entry in walker.build().filter_map(|entry| entry.ok()) {
+        if entry.depth() == 0 {
+            continue;
+        }
+        total += 1;
+        if lines.len() >= DIR_LISTING_LIMIT {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(working_dir).unwrap_or(entry.path());
+        let suffix = if entry.file_type().is_some_and(|ft| ft.is_dir()) { "/" } else { "" };
+        lines.push(format!(
+            "{}{}{}",
+            "  ".repeat(entry.depth() - 1),
+            relative.display(),
+            suffix
+        ));
+    }
+    if total > lines.len() {
+        lines.push(format!("... and {} more", total - lines.len()));
+    }
+    if lines.is_empty() {
+        lines.push("(empty)".to_string());
+    }
+    lines.join("\n")
+}
+
+fn max_bytes() -> usize {
+    std::env::var("ZARZ_MENTION_MAX_BYTES")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MENTION_MAX_BYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_mentions_outside_the_project() {
+        let root = std::env::temp_dir().join(format!("zarz-mentions-{}", std::process::id()));
+        let project = root.join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("notes.txt"), "inside").unwrap();
+        fs::write(root.join("secret.txt"), "outside").unwrap();
+
+        let absolute = root.join("secret.txt").canonicalize().unwrap();
+        let input = format!("see @notes.txt, @../secret.txt and @{}", absolute.display());
+        let attachments = collect_mentions(&input, &project);
+
+        assert_eq!(attachments.paths, vec!["notes.txt".to_string()]);
+        assert!(!attachments.to_context().contains("outside"));
+        let skipped = attachments
+            .notes
+            .iter()
+            .filter(|note| note.contains("outside the project directory"))
+            .count();
+        assert_eq!(skipped, 2);
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,63 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Looked up in the working directory, first match wins, when no file is given explicitly.
+const PROJECT_PROMPT_FILES: &[&str] = &[".zarz/system.md", "AGENTS.md"];
+
+/// Project conventions appended to the built-in system prompt.
+#[derive(Debug, Clone)]
+pub struct ProjectPrompt {
+    pub source: PathBuf,
+    pub content: String,
+}
+
+impl ProjectPrompt {
+    /// Read `explicit` (from `--system-prompt-file`), or else the first project prompt
+    /// file found in `working_dir`. An explicit file that can't be read is an error;
+    /// empty files are ignored.
+    pub fn load(explicit: Option<&Path>, working_dir: &Path) -> Result<Option<Self>> {
+        let (source, content) = match explicit {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read system prompt file {}", path.display()))?;
+                (path.to_path_buf(), content)
+            }
+            None => {
+                let found = PROJECT_PROMPT_FILES.iter().find_map(|name| {
+                    let path = working_dir.join(name);
+                    fs::read_to_string(&path).ok().map(|content| (PathBuf::from(name), content))
+                });
+                match found {
+                    Some(found) => found,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let content = content.trim().to_string();
+        if content.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { source, content }))
+    }
+
+    /// `base` followed by the project instructions.
+    pub fn apply(&self, base: &str) -> String {
+        format!(
+            "{}\n\n## Project instructions (from {})\n\n{}",
+            base.trim_end(),
+            self.source.display(),
+            self.content
+        )
+    }
+}
+
+/// `base` with the project prompt appended, if there is one.
+pub fn with_project_prompt(base: &str, project: Option<&ProjectPrompt>) -> String {
+    match project {
+        Some(project) => project.apply(base),
+        None => base.to_string(),
+    }
+}
@@ -57,13 +57,10 @@ impl AnthropicClient {
     }
 
     pub async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        super::note_ignored_seed(request, "Anthropic");
         let mut payload = serde_json::Map::new();
         payload.insert("model".to_string(), serde_json::Value::String(request.model.clone()));
-        payload.insert(
-            "max_tokens".to_string(),
-            serde_json::Value::Number(serde_json::Number::from(request.max_output_tokens)),
-        );
-        payload.insert("temperature".to_string(), json!(request.temperature));
+        apply_sampling(&mut payload, request);
         if let Some(system_prompt) = &request.system_prompt {
             payload.insert(
                 "system".to_string(),
@@ -90,6 +87,10 @@ impl AnthropicClient {
             );
         }
 
+        let mut payload = serde_json::Value::Object(payload);
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request("anthropic", &self.endpoint, &payload);
+
         let response = self
             .http
             .post(&self.endpoint)
@@ -108,6 +109,7 @@ impl AnthropicClient {
 
         let mut text = String::new();
         let mut tool_calls = Vec::new();
+        let mut thinking_blocks = Vec::new();
 
         for block in parsed.content {
             match block {
@@ -117,6 +119,16 @@ impl AnthropicClient {
                 AnthropicResponseBlock::ToolUse { id, name, input } => {
                     tool_calls.push(super::ToolCall { id, name, input });
                 }
+                AnthropicResponseBlock::Thinking { thinking, signature } => {
+                    thinking_blocks.push(json!({
+                        "type": "thinking",
+                        "thinking": thinking,
+                        "signature": signature,
+                    }));
+                }
+                AnthropicResponseBlock::RedactedThinking { data } => {
+                    thinking_blocks.push(json!({ "type": "redacted_thinking", "data": data }));
+                }
             }
         }
 
@@ -124,18 +136,17 @@ impl AnthropicClient {
             text,
             tool_calls,
             stop_reason: parsed.stop_reason,
+            usage: parsed.usage,
+            thinking_blocks,
         })
     }
 
     #[allow(dead_code)]
     pub async fn complete_stream(&self, request: &CompletionRequest) -> Result<CompletionStream> {
+        super::note_ignored_seed(request, "Anthropic");
         let mut payload = serde_json::Map::new();
         payload.insert("model".to_string(), serde_json::Value::String(request.model.clone()));
-        payload.insert(
-            "max_tokens".to_string(),
-            serde_json::Value::Number(serde_json::Number::from(request.max_output_tokens)),
-        );
-        payload.insert("temperature".to_string(), json!(request.temperature));
+        apply_sampling(&mut payload, request);
         payload.insert("stream".to_string(), json!(true));
 
         if let Some(system_prompt) = &request.system_prompt {
@@ -155,6 +166,10 @@ impl AnthropicClient {
             }]),
         );
 
+        let mut payload = serde_json::Value::Object(payload);
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request("anthropic", &self.endpoint, &payload);
+
         let response = self
             .http
             .post(&self.endpoint)
@@ -179,6 +194,28 @@ impl AnthropicClient {
     }
 }
 
+/// Set `max_tokens` and `temperature`, or the `thinking` block when extended thinking
+/// is on. Thinking tokens count against `max_tokens`, so the budget is added on top of
+/// the requested output, and Anthropic only accepts the default temperature with it.
+fn apply_sampling(payload: &mut serde_json::Map<String, serde_json::Value>, request: &CompletionRequest) {
+    match request.thinking_budget {
+        Some(budget) => {
+            payload.insert(
+                "max_tokens".to_string(),
+                json!(request.max_output_tokens.saturating_add(budget)),
+            );
+            payload.insert(
+                "thinking".to_string(),
+                json!({ "type": "enabled", "budget_tokens": budget }),
+            );
+        }
+        None => {
+            payload.insert("max_tokens".to_string(), json!(request.max_output_tokens));
+            payload.insert("temperature".to_string(), json!(request.temperature));
+        }
+    }
+}
+
 #[allow(dead_code)]
 fn parse_anthropic_sse_chunk(bytes: &Bytes) -> Result<String> {
     let text = String::from_utf8_lossy(bytes);
@@ -226,6 +263,8 @@ struct StreamDelta {
 struct AnthropicResponse {
     content: Vec<AnthropicResponseBlock>,
     stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<super::TokenUsage>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -239,4 +278,8 @@ pub enum AnthropicResponseBlock {
         name: String,
         input: serde_json::Value,
     },
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
 }
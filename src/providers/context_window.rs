@@ -0,0 +1,31 @@
+/// Used for models missing from the table.
+const DEFAULT_CONTEXT_TOKENS: usize = 128_000;
+
+// Matched by longest prefix, like the pricing table.
+const CONTEXT_WINDOW_TABLE: &[(&str, usize)] = &[
+    ("claude-", 200_000),
+    ("gpt-5", 400_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("glm-4.6", 200_000),
+    ("glm-4.5", 128_000),
+];
+
+/// Context window of `model` in tokens; `ZARZ_CONTEXT_TOKENS` overrides the table.
+pub fn context_window_for(model: &str) -> usize {
+    if let Some(tokens) = std::env::var("ZARZ_CONTEXT_TOKENS")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .filter(|tokens| *tokens > 0)
+    {
+        return tokens;
+    }
+
+    let model = model.to_ascii_lowercase();
+    CONTEXT_WINDOW_TABLE
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, tokens)| *tokens)
+        .unwrap_or(DEFAULT_CONTEXT_TOKENS)
+}
@@ -73,6 +73,12 @@ impl GlmClient {
             "max_tokens": request.max_output_tokens,
             "messages": messages,
         });
+        if let Some(seed) = request.seed {
+            payload["seed"] = json!(seed);
+        }
+        if let Some(enabled) = request.glm_thinking {
+            payload["thinking"] = json!({ "type": if enabled { "enabled" } else { "disabled" } });
+        }
 
         if let Some(tools) = &request.tools {
             let glm_tools: Vec<_> = tools.iter().map(|tool| {
@@ -91,6 +97,9 @@ impl GlmClient {
         // Construct full endpoint URL
         let full_url = format!("{}/chat/completions", self.endpoint);
 
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request("glm", &full_url, &payload);
+
         let response = self
             .http
             .post(&full_url)
@@ -114,6 +123,7 @@ impl GlmClient {
             .await
             .context("Failed to decode GLM response")?;
 
+        let usage = parsed.usage;
         let first_choice = parsed.choices.into_iter().next()
             .ok_or_else(|| anyhow!("GLM response did not include any choices"))?;
 
@@ -138,6 +148,8 @@ impl GlmClient {
             text,
             tool_calls,
             stop_reason: first_choice.finish_reason,
+            usage,
+            thinking_blocks: Vec::new(),
         })
     }
 
@@ -155,16 +167,22 @@ impl GlmClient {
             "content": request.user_prompt,
         }));
 
-        let payload = json!({
+        let mut payload = json!({
             "model": request.model,
             "max_tokens": request.max_output_tokens,
             "messages": messages,
             "stream": true,
         });
+        if let Some(seed) = request.seed {
+            payload["seed"] = json!(seed);
+        }
 
         // Construct full endpoint URL
         let full_url = format!("{}/chat/completions", self.endpoint);
 
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request("glm", &full_url, &payload);
+
         let response = self
             .http
             .post(&full_url)
@@ -233,6 +251,8 @@ struct StreamDelta {
 #[derive(Debug, Deserialize)]
 struct GlmResponse {
     choices: Vec<GlmChoice>,
+    #[serde(default)]
+    usage: Option<super::TokenUsage>,
 }
 
 #[derive(Debug, Deserialize)]
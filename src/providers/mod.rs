@@ -4,12 +4,16 @@ use futures::Stream;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
 use crate::cli::Provider;
 
 mod anthropic;
 mod openai;
 mod glm;
+pub mod context_window;
+pub mod pricing;
 
 #[derive(Debug, Clone)]
 pub struct CompletionRequest {
@@ -21,14 +25,189 @@ pub struct CompletionRequest {
     pub messages: Option<Vec<Value>>,
     pub tools: Option<Vec<Value>>,
     pub reasoning_effort: Option<ReasoningEffort>,
+    /// Sampling seed; honored by OpenAI chat completions and GLM, ignored by Anthropic.
+    pub seed: Option<u64>,
+    /// Raw fields merged into the provider payload last (`--extra` / `[provider_extra]`).
+    pub extra_params: Option<ExtraParams>,
+    /// Extended-thinking budget in tokens; honored by Anthropic, ignored elsewhere.
+    pub thinking_budget: Option<u32>,
+    /// GLM deep thinking on or off; `None` leaves the model default. Ignored elsewhere.
+    pub glm_thinking: Option<bool>,
+    /// Bumped once per streamed output delta by providers that stream, so the REPL
+    /// spinner can show tokens arriving. `None` outside the REPL.
+    pub stream_tokens: Option<Arc<AtomicUsize>>,
 }
 
+pub type ExtraParams = serde_json::Map<String, Value>;
+
+/// Payload fields ZarzCLI sets itself. Extra params may still override them, but doing
+/// so can break requests, so callers warn about it.
+pub const MANAGED_REQUEST_FIELDS: &[&str] = &[
+    "model",
+    "messages",
+    "input",
+    "instructions",
+    "system",
+    "tools",
+    "tool_choice",
+    "stream",
+    "max_tokens",
+    "max_output_tokens",
+    "temperature",
+    "seed",
+    "reasoning",
+    "reasoning_effort",
+    "thinking",
+];
+
 #[derive(Debug, Clone)]
 pub struct CompletionResponse {
     pub text: String,
     pub tool_calls: Vec<ToolCall>,
-    #[allow(dead_code)]
     pub stop_reason: Option<String>,
+    pub usage: Option<TokenUsage>,
+    /// Anthropic `thinking` / `redacted_thinking` blocks, verbatim with their signatures
+    /// so they can be sent back alongside tool results.
+    pub thinking_blocks: Vec<Value>,
+}
+
+/// Token counts reported by a provider for a single completion.
+///
+/// Anthropic and the OpenAI Responses API report `input_tokens` / `output_tokens`;
+/// Chat Completions-style APIs use `prompt_tokens` / `completion_tokens`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default, alias = "prompt_tokens")]
+    pub input_tokens: u64,
+    #[serde(default, alias = "completion_tokens")]
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+impl CompletionResponse {
+    /// Whether the returned tool calls should be executed and the model asked for a follow-up.
+    ///
+    /// Anthropic reports `tool_use` when it is waiting on tool results; `end_turn` and
+    /// `max_tokens` finish the turn even if (possibly truncated) tool blocks were returned;
+    /// an incomplete OpenAI Responses payload reports `max_output_tokens` and Chat
+    /// Completions reports `length` the same way. Providers without a recognised stop
+    /// reason fall back to the presence of tool calls.
+    pub fn wants_tool_execution(&self) -> bool {
+        match self.stop_reason.as_deref() {
+            Some("tool_use") => !self.tool_calls.is_empty(),
+            Some("end_turn")
+            | Some("max_tokens")
+            | Some("max_output_tokens")
+            | Some("length")
+            | Some("stop_sequence") => false,
+            _ => !self.tool_calls.is_empty(),
+        }
+    }
+
+    /// The readable part of the model's extended thinking, if any.
+    pub fn thinking_text(&self) -> String {
+        self.thinking_blocks
+            .iter()
+            .filter_map(|block| block.get("thinking").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Whether generation stopped because the output token limit was reached.
+    pub fn hit_token_limit(&self) -> bool {
+        matches!(
+            self.stop_reason.as_deref(),
+            Some("max_tokens") | Some("length") | Some("max_output_tokens")
+        )
+    }
+
+    /// The warning shown when the response was cut off at the `max_tokens` output limit.
+    pub fn truncation_warning(&self, max_tokens: u32) -> Option<String> {
+        if !self.hit_token_limit() {
+            return None;
+        }
+        let mut warning = format!(
+            "Warning: response stopped at the {}-token output limit and may be incomplete.",
+            max_tokens
+        );
+        if !self.tool_calls.is_empty() {
+            warning.push_str(&format!(
+                "\nSkipped {} tool call(s) whose arguments may have been truncated.",
+                self.tool_calls.len()
+            ));
+        }
+        Some(warning)
+    }
+}
+
+/// Model-name suffix that turns on Anthropic extended thinking, as in
+/// `claude-sonnet-4-5-20250929-thinking`.
+const THINKING_MODEL_SUFFIX: &str = "-thinking";
+const DEFAULT_THINKING_BUDGET: u32 = 10_000;
+/// Anthropic rejects smaller thinking budgets.
+const MIN_THINKING_BUDGET: u32 = 1_024;
+
+/// Split a `-thinking` Anthropic model alias into the real model id and a thinking
+/// budget (`ZARZ_THINKING_BUDGET`). Other providers keep the name unchanged, since a
+/// self-hosted model may legitimately end in `-thinking`.
+pub fn split_thinking_model(model: &str, provider: &Provider) -> (String, Option<u32>) {
+    match model.strip_suffix(THINKING_MODEL_SUFFIX) {
+        Some(base) if *provider == Provider::Anthropic => {
+            let budget = std::env::var("ZARZ_THINKING_BUDGET")
+                .ok()
+                .and_then(|raw| raw.trim().parse::<u32>().ok())
+                .unwrap_or(DEFAULT_THINKING_BUDGET)
+                .max(MIN_THINKING_BUDGET);
+            (base.to_string(), Some(budget))
+        }
+        _ => (model.to_string(), None),
+    }
+}
+
+/// Marks a request the provider refused with 401 Unauthorized, usually an expired
+/// access token.
+#[derive(Debug)]
+pub(crate) struct Unauthorized;
+
+impl std::fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("credentials were rejected (401 Unauthorized)")
+    }
+}
+
+impl std::error::Error for Unauthorized {}
+
+/// Whether a failed request was refused as unauthorized (HTTP 401).
+pub fn is_unauthorized(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<Unauthorized>()
+            || cause
+                .downcast_ref::<reqwest::Error>()
+                .and_then(reqwest::Error::status)
+                == Some(reqwest::StatusCode::UNAUTHORIZED)
+    })
+}
+
+/// Emit a debug note when a request carries a seed the provider cannot honor.
+pub(crate) fn note_ignored_seed(request: &CompletionRequest, provider: &str) {
+    if request.seed.is_some() && std::env::var_os("ZARZ_DEBUG").is_some() {
+        eprintln!("[debug] {} does not support seeds; ignoring --seed", provider);
+    }
+}
+
+/// Merge `request.extra_params` into an outgoing JSON payload, overriding existing keys.
+pub(crate) fn apply_extra_params(payload: &mut Value, request: &CompletionRequest) {
+    if let (Some(extra), Some(fields)) = (&request.extra_params, payload.as_object_mut()) {
+        for (key, value) in extra {
+            fields.insert(key.clone(), value.clone());
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -56,6 +235,16 @@ impl ReasoningEffort {
             Self::High => "high",
         }
     }
+
+    pub fn from_str(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "minimal" => Some(Self::Minimal),
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -93,13 +282,16 @@ impl ProviderClient {
             Provider::Glm => Ok(Self::Glm(
                 glm::GlmClient::from_env(api_key, endpoint_override, timeout_override)?,
             )),
+            Provider::OpenAiCompatible => Ok(Self::OpenAi(
+                openai::OpenAiClient::compatible(api_key, endpoint_override, timeout_override)?,
+            )),
         }
     }
 
     pub fn name(&self) -> &'static str {
         match self {
             ProviderClient::Anthropic(_) => "anthropic",
-            ProviderClient::OpenAi(_) => "openai",
+            ProviderClient::OpenAi(client) => client.provider_name(),
             ProviderClient::Glm(_) => "glm",
         }
     }
@@ -108,11 +300,14 @@ impl ProviderClient {
 #[async_trait]
 impl CompletionProvider for ProviderClient {
     async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
-        match self {
+        let started = std::time::Instant::now();
+        let result = match self {
             ProviderClient::Anthropic(client) => client.complete(request).await,
             ProviderClient::OpenAi(client) => client.complete(request).await,
             ProviderClient::Glm(client) => client.complete(request).await,
-        }
+        };
+        crate::debug_log::provider_response(self.name(), &request.model, &result, started.elapsed());
+        result
     }
 
     async fn complete_stream(&self, request: &CompletionRequest) -> Result<CompletionStream> {
@@ -123,3 +318,73 @@ impl CompletionProvider for ProviderClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(stop_reason: Option<&str>, tool_calls: usize) -> CompletionResponse {
+        CompletionResponse {
+            text: String::new(),
+            tool_calls: (0..tool_calls)
+                .map(|i| ToolCall {
+                    id: format!("call_{}", i),
+                    name: "read_file".to_string(),
+                    input: json!({"path": "src/main.rs"}),
+                })
+                .collect(),
+            stop_reason: stop_reason.map(str::to_string),
+            usage: None,
+            thinking_blocks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn anthropic_tool_use_runs_tools() {
+        assert!(response(Some("tool_use"), 1).wants_tool_execution());
+        assert!(!response(Some("tool_use"), 0).wants_tool_execution());
+        assert!(!response(Some("tool_use"), 1).hit_token_limit());
+    }
+
+    #[test]
+    fn anthropic_end_turn_and_stop_sequence_finish_the_turn() {
+        for reason in ["end_turn", "stop_sequence"] {
+            let response = response(Some(reason), 1);
+            assert!(!response.wants_tool_execution(), "{}", reason);
+            assert!(!response.hit_token_limit(), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn anthropic_max_tokens_drops_truncated_tool_calls() {
+        let response = response(Some("max_tokens"), 1);
+        assert!(!response.wants_tool_execution());
+        assert!(response.hit_token_limit());
+    }
+
+    #[test]
+    fn openai_finish_reasons() {
+        assert!(response(Some("tool_calls"), 2).wants_tool_execution());
+        assert!(!response(Some("stop"), 0).wants_tool_execution());
+        assert!(!response(Some("stop"), 0).hit_token_limit());
+
+        let truncated = response(Some("length"), 1);
+        assert!(!truncated.wants_tool_execution());
+        assert!(truncated.hit_token_limit());
+    }
+
+    #[test]
+    fn openai_responses_incomplete_hits_token_limit() {
+        let response = response(Some("max_output_tokens"), 1);
+        assert!(!response.wants_tool_execution());
+        assert!(response.hit_token_limit());
+    }
+
+    #[test]
+    fn missing_stop_reason_falls_back_to_tool_calls() {
+        assert!(response(None, 1).wants_tool_execution());
+        assert!(!response(None, 0).wants_tool_execution());
+        assert!(!response(None, 1).hit_token_limit());
+    }
+}
@@ -6,8 +6,9 @@ use reqwest::{Client, StatusCode};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use super::{CompletionRequest, CompletionResponse, CompletionStream, ReasoningEffort, ToolCall};
+use super::{CompletionRequest, CompletionResponse, CompletionStream, ReasoningEffort, TokenUsage, ToolCall, Unauthorized};
 
 #[derive(Debug)]
 enum ResponsesCallError {
@@ -21,6 +22,17 @@ impl From<anyhow::Error> for ResponsesCallError {
     }
 }
 
+/// An error status from the Responses API, tagged `Unauthorized` on a 401 so the
+/// caller can refresh credentials and retry.
+fn responses_status_error(status: StatusCode, body: &str) -> anyhow::Error {
+    let message = format!("OpenAI Responses returned status {}: {}", status, body.trim());
+    if status == StatusCode::UNAUTHORIZED {
+        anyhow::Error::new(Unauthorized).context(message)
+    } else {
+        anyhow!(message)
+    }
+}
+
 fn generate_session_id() -> String {
     thread_rng()
         .sample_iter(&Alphanumeric)
@@ -51,6 +63,7 @@ const DEFAULT_RESPONSES_ENDPOINT: &str = "https://api.openai.com/v1/responses";
 const DEFAULT_CHAT_ENDPOINT: &str = "https://api.openai.com/v1/chat/completions";
 const CHATGPT_RESPONSES_ENDPOINT: &str = "https://chatgpt.com/backend-api/codex/responses";
 const CHATGPT_CHAT_ENDPOINT: &str = "https://chatgpt.com/backend-api/chat/completions";
+const DEFAULT_COMPATIBLE_BASE_URL: &str = "http://localhost:11434/v1";
 const ORIGINATOR_HEADER: &str = "zarz_cli";
 const CHATGPT_ORIGINATOR_HEADER: &str = "codex_cli_rs";
 const CHATGPT_CODEX_INSTRUCTIONS: &str = include_str!("../prompts/codex_instructions.md");
@@ -62,6 +75,8 @@ pub struct OpenAiClient {
     api_key: String,
     is_chatgpt_backend: bool,
     session_id: Option<String>,
+    /// Skip the Responses API entirely; set for OpenAI-compatible servers.
+    chat_only: bool,
 }
 
 impl OpenAiClient {
@@ -165,10 +180,72 @@ impl OpenAiClient {
             api_key,
             is_chatgpt_backend,
             session_id,
+            chat_only: false,
+        })
+    }
+
+    /// Client for a self-hosted OpenAI-compatible server (Ollama, LM Studio, LiteLLM).
+    /// Only Chat Completions is used, and the API key may be empty.
+    pub fn compatible(
+        api_key_override: Option<String>,
+        endpoint_override: Option<String>,
+        timeout_override: Option<u64>,
+    ) -> Result<Self> {
+        let api_key = api_key_override.unwrap_or_default();
+        let base_url = endpoint_override
+            .or_else(|| std::env::var("OPENAI_COMPATIBLE_BASE_URL").ok())
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_COMPATIBLE_BASE_URL.to_string());
+        let base_url = base_url.trim().trim_end_matches('/');
+        let chat_endpoint = if base_url.ends_with("/chat/completions") {
+            base_url.to_string()
+        } else {
+            format!("{}/chat/completions", base_url)
+        };
+
+        let timeout_secs = timeout_override
+            .or_else(|| {
+                std::env::var("OPENAI_TIMEOUT_SECS")
+                    .ok()
+                    .and_then(|raw| raw.parse::<u64>().ok())
+            })
+            .unwrap_or(300);
+
+        let http = Client::builder()
+            .user_agent("zarz-cli/0.1")
+            .timeout(std::time::Duration::from_secs(timeout_secs))
+            .build()
+            .context("Failed to build HTTP client for OpenAI-compatible server")?;
+
+        Ok(Self {
+            http,
+            responses_endpoint: String::new(),
+            chat_endpoint,
+            api_key,
+            is_chatgpt_backend: false,
+            session_id: None,
+            chat_only: true,
         })
     }
 
+    pub fn provider_name(&self) -> &'static str {
+        if self.chat_only { "openai-compatible" } else { "openai" }
+    }
+
+    /// Attach the bearer token, leaving it off when a keyless local server is in use.
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if self.api_key.trim().is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(&self.api_key)
+        }
+    }
+
     pub async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse> {
+        if self.chat_only {
+            return self.complete_via_chat(request).await;
+        }
+
         if self.is_chatgpt_backend {
             return match self.complete_via_responses(request).await {
                 Ok(result) => Ok(result),
@@ -194,6 +271,7 @@ impl OpenAiClient {
         &self,
         request: &CompletionRequest,
     ) -> Result<CompletionResponse, ResponsesCallError> {
+        super::note_ignored_seed(request, "OpenAI Responses API");
         let instructions = request.system_prompt.clone().unwrap_or_default();
         let mut input_items = build_responses_input(&request.messages, &request.user_prompt);
         let tools = build_responses_tools(request.tools.as_ref());
@@ -244,6 +322,9 @@ impl OpenAiClient {
 
         payload["input"] = json!(input_items);
 
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request("openai", &self.responses_endpoint, &payload);
+
         let response = self
             .http
             .post(&self.responses_endpoint)
@@ -256,8 +337,7 @@ impl OpenAiClient {
         let status = response.status();
 
         if self.is_chatgpt_backend {
-            let body_text = response
-                .text()
+            let body_text = read_sse_body(response, request.stream_tokens.as_deref())
                 .await
                 .context("Failed to read OpenAI Responses payload")?;
 
@@ -272,11 +352,7 @@ impl OpenAiClient {
                     )));
                 }
 
-                return Err(ResponsesCallError::Other(anyhow!(
-                    "OpenAI Responses returned status {}: {}",
-                    status,
-                    body_text.trim()
-                )));
+                return Err(ResponsesCallError::Other(responses_status_error(status, &body_text)));
             }
 
             let body = extract_sse_response(&body_text)
@@ -300,11 +376,7 @@ impl OpenAiClient {
                     )));
                 }
 
-                return Err(ResponsesCallError::Other(anyhow!(
-                    "OpenAI Responses returned status {}: {}",
-                    status,
-                    body_text.trim()
-                )));
+                return Err(ResponsesCallError::Other(responses_status_error(status, &body_text)));
             }
 
             let body: Value = serde_json::from_slice(&body_bytes)
@@ -339,6 +411,9 @@ impl OpenAiClient {
             "temperature": request.temperature,
             "messages": messages,
         });
+        if let Some(seed) = request.seed {
+            payload["seed"] = json!(seed);
+        }
 
         if let Some(tools) = &request.tools {
             let openai_tools: Vec<_> = tools
@@ -357,10 +432,11 @@ impl OpenAiClient {
             payload["tools"] = json!(openai_tools);
         }
 
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request(self.provider_name(), &self.chat_endpoint, &payload);
+
         let response = self
-            .http
-            .post(&self.chat_endpoint)
-            .bearer_auth(&self.api_key)
+            .authorize(self.http.post(&self.chat_endpoint))
             .json(&payload)
             .send()
             .await
@@ -375,6 +451,7 @@ impl OpenAiClient {
             .await
             .context("Failed to decode OpenAI Chat Completions response")?;
 
+        let usage = parsed.usage;
         let first_choice = parsed
             .choices
             .into_iter()
@@ -402,6 +479,8 @@ impl OpenAiClient {
             text,
             tool_calls,
             stop_reason: first_choice.finish_reason,
+            usage,
+            thinking_blocks: Vec::new(),
         })
     }
 
@@ -423,18 +502,22 @@ impl OpenAiClient {
             "content": request.user_prompt,
         }));
 
-        let payload = json!({
+        let mut payload = json!({
             "model": request.model,
             "max_tokens": request.max_output_tokens,
             "temperature": request.temperature,
             "messages": messages,
             "stream": true,
         });
+        if let Some(seed) = request.seed {
+            payload["seed"] = json!(seed);
+        }
+
+        super::apply_extra_params(&mut payload, request);
+        crate::debug_log::provider_request(self.provider_name(), &self.chat_endpoint, &payload);
 
         let response = self
-            .http
-            .post(&self.chat_endpoint)
-            .bearer_auth(&self.api_key)
+            .authorize(self.http.post(&self.chat_endpoint))
             .json(&payload)
             .send()
             .await
@@ -475,6 +558,21 @@ fn build_responses_input(messages: &Option<Vec<Value>>, fallback_prompt: &str) -
     })]
 }
 
+/// Chat Completions content parts (`text`, `image_url`) in their Responses API form.
+fn responses_content_item(item: &Value, role: &str) -> Value {
+    match item.get("type").and_then(|v| v.as_str()) {
+        Some("text") => json!({
+            "type": if role == "assistant" { "output_text" } else { "input_text" },
+            "text": item.get("text").cloned().unwrap_or(Value::String(String::new())),
+        }),
+        Some("image_url") => json!({
+            "type": "input_image",
+            "image_url": item.pointer("/image_url/url").cloned().unwrap_or(Value::Null),
+        }),
+        _ => item.clone(),
+    }
+}
+
 fn append_responses_items_from_chat_message(message: &Value, items: &mut Vec<Value>) {
     let Some(role) = message.get("role").and_then(|v| v.as_str()) else {
         return;
@@ -503,8 +601,8 @@ fn append_responses_items_from_chat_message(message: &Value, items: &mut Vec<Val
 
     let content = message.get("content").unwrap_or(&Value::Null);
 
-    let content_items = if content.is_array() {
-        content.as_array().cloned().unwrap_or_default()
+    let content_items = if let Some(items) = content.as_array() {
+        items.iter().map(|item| responses_content_item(item, role)).collect()
     } else if let Some(text) = content.as_str() {
         let kind = if role == "assistant" {
             "output_text"
@@ -652,11 +750,33 @@ fn parse_responses_completion(body: Value) -> Result<CompletionResponse> {
         }
     }
 
+    let usage = body
+        .get("usage")
+        .or_else(|| body.get("response").and_then(|r| r.get("usage")))
+        .and_then(|value| serde_json::from_value::<TokenUsage>(value.clone()).ok());
+
+    // A truncated response reports `status: "incomplete"` with the cause in
+    // `incomplete_details.reason` (e.g. `max_output_tokens`).
+    let response_body = body.get("response").unwrap_or(&body);
+    let stop_reason = match response_body.get("status").and_then(|v| v.as_str()) {
+        Some("incomplete") => Some(
+            response_body
+                .get("incomplete_details")
+                .and_then(|details| details.get("reason"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("incomplete")
+                .to_string(),
+        ),
+        _ => None,
+    };
+
     let text = text_parts.join("\n");
     Ok(CompletionResponse {
         text,
         tool_calls,
-        stop_reason: None,
+        stop_reason,
+        usage,
+        thinking_blocks: Vec::new(),
     })
 }
 
@@ -664,6 +784,29 @@ fn normalize_tool_arguments(payload: &str) -> Value {
     serde_json::from_str(payload).unwrap_or_else(|_| Value::String(payload.to_string()))
 }
 
+/// Read a streamed Responses body to the end, bumping `progress` once per `*.delta`
+/// event (roughly one token each) as events arrive.
+async fn read_sse_body(response: reqwest::Response, progress: Option<&AtomicUsize>) -> Result<String> {
+    let mut body = Vec::new();
+    // Everything before this offset ends in a complete event that has been counted
+    let mut counted = 0;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        body.extend_from_slice(&chunk?);
+        let Some(progress) = progress else {
+            continue;
+        };
+        while let Some(end) = body[counted..].windows(2).position(|window| window == b"\n\n") {
+            let event = &body[counted..counted + end];
+            if event.windows(7).any(|window| window == b".delta\"") {
+                progress.fetch_add(1, Ordering::Relaxed);
+            }
+            counted += end + 2;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
 #[allow(dead_code)]
 fn parse_openai_sse_chunk(bytes: &Bytes) -> Result<String> {
     let text = String::from_utf8_lossy(bytes);
@@ -709,6 +852,8 @@ struct StreamDelta {
 #[derive(Debug, Deserialize)]
 struct OpenAiResponse {
     choices: Vec<OpenAiChoice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -740,3 +885,59 @@ struct OpenAiFunction {
     name: String,
     arguments: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn incomplete_responses_payload_reports_the_token_limit() {
+        let body = json!({
+            "id": "resp_1",
+            "status": "incomplete",
+            "incomplete_details": {"reason": "max_output_tokens"},
+            "output": [
+                {
+                    "type": "message",
+                    "content": [{"type": "output_text", "text": "Here is the start of"}]
+                },
+                {
+                    "type": "function_call",
+                    "call_id": "call_1",
+                    "name": "write_file",
+                    "arguments": "{\"path\": \"src/li"
+                }
+            ],
+            "usage": {"input_tokens": 12, "output_tokens": 64}
+        });
+
+        let response = parse_responses_completion(body).unwrap();
+        assert_eq!(response.stop_reason.as_deref(), Some("max_output_tokens"));
+        assert_eq!(response.text, "Here is the start of");
+        assert_eq!(response.tool_calls.len(), 1);
+        assert!(!response.wants_tool_execution());
+
+        let warning = response.truncation_warning(64).unwrap();
+        assert!(warning.contains("stopped at the 64-token output limit"));
+        assert!(warning.contains("Skipped 1 tool call(s)"));
+    }
+
+    #[test]
+    fn completed_responses_payload_has_no_warning() {
+        let body = json!({
+            "response": {
+                "status": "completed",
+                "output": [{
+                    "type": "message",
+                    "content": [{"type": "output_text", "text": "Done."}]
+                }]
+            }
+        });
+
+        let response = parse_responses_completion(body).unwrap();
+        assert_eq!(response.stop_reason, None);
+        assert_eq!(response.text, "Done.");
+        assert_eq!(response.truncation_warning(64), None);
+    }
+}
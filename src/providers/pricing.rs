@@ -0,0 +1,77 @@
+use super::TokenUsage;
+
+/// USD rates per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPricing {
+    pub fn estimate(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_million
+            + usage.output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+// Matched by longest prefix so dated snapshots (e.g. `claude-sonnet-4-5-20250929`)
+// resolve to their family rate.
+const PRICING_TABLE: &[(&str, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-haiku-4-5", 1.0, 5.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("gpt-5.1-codex-mini", 0.25, 2.0),
+    ("gpt-5.1-codex", 1.25, 10.0),
+    ("gpt-5.1", 1.25, 10.0),
+    ("gpt-5-codex", 1.25, 10.0),
+    ("gpt-5-mini", 0.25, 2.0),
+    ("gpt-5-nano", 0.05, 0.4),
+    ("gpt-5", 1.25, 10.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("glm-4.6", 0.6, 2.2),
+    ("glm-4.5-air", 0.2, 1.1),
+    ("glm-4.5", 0.6, 2.2),
+];
+
+/// Look up rates for `model`, applying `ZARZ_PRICE_IN` / `ZARZ_PRICE_OUT` overrides.
+///
+/// Returns `None` when the model is not in the table and the overrides don't supply
+/// both rates.
+pub fn pricing_for(model: &str) -> Option<ModelPricing> {
+    let env_rate = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<f64>().ok())
+    };
+    let input_override = env_rate("ZARZ_PRICE_IN");
+    let output_override = env_rate("ZARZ_PRICE_OUT");
+
+    let model = model.to_ascii_lowercase();
+    let known = PRICING_TABLE
+        .iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(_, input, output)| ModelPricing {
+            input_per_million: *input,
+            output_per_million: *output,
+        });
+
+    match (known, input_override, output_override) {
+        (Some(rates), input, output) => Some(ModelPricing {
+            input_per_million: input.unwrap_or(rates.input_per_million),
+            output_per_million: output.unwrap_or(rates.output_per_million),
+        }),
+        (None, Some(input), Some(output)) => Some(ModelPricing {
+            input_per_million: input,
+            output_per_million: output,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use dialoguer::{theme::ColorfulTheme, Password};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+
+/// Prefix marking a config value as ciphertext rather than a plaintext key.
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Passphrase and the key derived from it for a given salt, so a session only
+/// prompts once no matter how often the config is loaded or saved.
+struct CachedKey {
+    passphrase: String,
+    salt: String,
+    key: [u8; 32],
+}
+
+static KEY_CACHE: Mutex<Option<CachedKey>> = Mutex::new(None);
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Generate a fresh base64 salt for deriving the config encryption key.
+pub fn new_salt() -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow!("Failed to generate encryption salt"))?;
+    Ok(STANDARD.encode(salt))
+}
+
+pub fn encrypt(plaintext: &str, salt: &str) -> Result<String> {
+    let key = derive_key(salt, true)?;
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce_bytes)
+        .map_err(|_| anyhow!("Failed to generate encryption nonce"))?;
+
+    let mut buffer = plaintext.as_bytes().to_vec();
+    sealing_key(&key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), Aad::empty(), &mut buffer)
+        .map_err(|_| anyhow!("Failed to encrypt API key"))?;
+
+    let mut payload = nonce_bytes.to_vec();
+    payload.extend(buffer);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+}
+
+pub fn decrypt(value: &str, salt: &str) -> Result<String> {
+    let encoded = value
+        .strip_prefix(ENCRYPTED_PREFIX)
+        .context("Value is not an encrypted config entry")?;
+    let payload = STANDARD
+        .decode(encoded)
+        .context("Encrypted config entry is not valid base64")?;
+    if payload.len() < NONCE_LEN {
+        bail!("Encrypted config entry is truncated");
+    }
+
+    let key = derive_key(salt, false)?;
+    let (nonce_bytes, ciphertext) = payload.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+        .map_err(|_| anyhow!("Encrypted config entry has an invalid nonce"))?;
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = sealing_key(&key)?
+        .open_in_place(nonce, Aad::empty(), &mut buffer)
+        .map_err(|_| {
+            // A wrong passphrase must not stay cached, or every retry fails the same way
+            *KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            anyhow!("Failed to decrypt stored API keys (wrong passphrase?)")
+        })?;
+
+    String::from_utf8(plaintext.to_vec()).context("Decrypted API key is not valid UTF-8")
+}
+
+fn sealing_key(key: &[u8; 32]) -> Result<LessSafeKey> {
+    let unbound = UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("Invalid encryption key"))?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+fn derive_key(salt: &str, confirm_new: bool) -> Result<[u8; 32]> {
+    let mut cache = KEY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cached) = cache.as_ref()
+        && cached.salt == salt
+    {
+        return Ok(cached.key);
+    }
+
+    let passphrase = match cache.as_ref() {
+        Some(cached) => cached.passphrase.clone(),
+        None => read_passphrase(confirm_new)?,
+    };
+    let salt_bytes = STANDARD.decode(salt).context("Config key_salt is not valid base64")?;
+
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("iteration count is non-zero"),
+        &salt_bytes,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+
+    *cache = Some(CachedKey {
+        passphrase,
+        salt: salt.to_string(),
+        key,
+    });
+    Ok(key)
+}
+
+/// Read the passphrase from `ZARZ_CONFIG_PASSPHRASE`, falling back to a hidden prompt.
+fn read_passphrase(confirm_new: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("ZARZ_CONFIG_PASSPHRASE")
+        && !passphrase.is_empty()
+    {
+        return Ok(passphrase);
+    }
+
+    if !crate::interactive::is_interactive() {
+        bail!("API keys in the config are encrypted; set ZARZ_CONFIG_PASSPHRASE to unlock them");
+    }
+
+    let theme = ColorfulTheme::default();
+    let mut prompt = Password::with_theme(&theme).with_prompt("Config passphrase");
+    if confirm_new {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+    prompt.interact().context("Failed to read config passphrase")
+}
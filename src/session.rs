@@ -2,8 +2,10 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use similar::{ChangeTag, TextDiff};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use crate::intelligence::ProjectIntelligence;
 
@@ -13,6 +15,8 @@ pub struct Message {
     pub content: String,
     #[serde(default)]
     pub metadata: Option<MessageMetadata>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -58,6 +62,27 @@ impl MessageMetadata {
     }
 }
 
+/// A `/compact` summary standing in for the first `covers` messages of the history.
+#[derive(Debug, Clone)]
+pub struct HistorySummary {
+    pub covers: usize,
+    pub message: Message,
+}
+
+impl HistorySummary {
+    pub fn new(covers: usize, summary: &str) -> Self {
+        Self {
+            covers,
+            message: Message {
+                role: MessageRole::System,
+                content: format!("Summary of {} earlier messages:\n{}", covers, summary),
+                metadata: None,
+                created_at: Some(Utc::now()),
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PendingChange {
     pub path: PathBuf,
@@ -65,17 +90,119 @@ pub struct PendingChange {
     pub new_content: String,
 }
 
+/// What happened to a file over a session, judged against its content before the
+/// session first changed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl FileChangeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileChangeKind::Created => "created",
+            FileChangeKind::Modified => "modified",
+            FileChangeKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// A file the session changed, with line counts; saved with the session for `/resume`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChangeRecord {
+    pub path: PathBuf,
+    pub kind: FileChangeKind,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Bounds on how much disk work refreshing loaded files may do per turn.
+#[derive(Debug, Clone, Copy)]
+pub struct FileReloadLimits {
+    pub max_files_per_turn: usize,
+    pub max_file_bytes: u64,
+    pub debounce: Duration,
+}
+
+impl FileReloadLimits {
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|raw| raw.parse::<u64>().ok())
+        };
+
+        Self {
+            max_files_per_turn: env_u64("ZARZ_RELOAD_MAX_FILES").unwrap_or(20) as usize,
+            max_file_bytes: env_u64("ZARZ_RELOAD_MAX_BYTES").unwrap_or(512 * 1024),
+            debounce: Duration::from_millis(env_u64("ZARZ_RELOAD_DEBOUNCE_MS").unwrap_or(500)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FileReloadReport {
+    pub reloaded: Vec<PathBuf>,
+    pub skipped_large: Vec<(PathBuf, u64)>,
+    pub deferred: Vec<PathBuf>,
+}
+
+const ELIDED_TOOL_OUTPUT: &str = "[earlier tool output omitted]";
+
+/// Rough token count for budgeting; about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// The part of the history that fits the context limit. Older tool outputs are
+/// elided first, then whole turns are dropped from the start; the latest turn is
+/// always kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ContextWindow {
+    /// Messages before this index are left out of the prompt.
+    pub start: usize,
+    /// Tool outputs before this index are replaced with a marker.
+    pub elide_before: usize,
+    pub dropped_messages: usize,
+    /// Tool outputs elided to fit the limit (beyond `ZARZ_TOOL_OUTPUT_TURNS`).
+    pub elided_outputs: usize,
+    pub estimated_tokens: usize,
+}
+
+impl ContextWindow {
+    pub fn truncated(&self) -> bool {
+        self.dropped_messages > 0 || self.elided_outputs > 0
+    }
+}
+
 #[derive(Debug)]
 pub struct Session {
     pub conversation_history: Vec<Message>,
     pub current_files: HashMap<PathBuf, String>,
+    file_mtimes: HashMap<PathBuf, SystemTime>,
+    /// Only resend tool outputs from the last N user turns; older ones are replaced
+    /// with a marker in the prompt but kept in the persisted history.
+    pub tool_output_turns: Option<usize>,
+    /// Estimated tokens the transcript and loaded files may use in a prompt.
+    pub context_limit: Option<usize>,
     pub pending_changes: Vec<PendingChange>,
+    /// Content of every file changed this session from before its first change (`None`
+    /// if it did not exist yet), keyed by absolute path.
+    change_baselines: BTreeMap<PathBuf, Option<String>>,
+    /// File changes saved by the session this one was resumed from.
+    pub resumed_file_changes: Vec<FileChangeRecord>,
     pub project_intelligence: ProjectIntelligence,
     pub working_directory: PathBuf,
     pub storage_id: Option<String>,
     pub title: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Summary saved in place of the oldest messages once the history outgrows the
+    /// persisted-message cap; the in-memory history keeps them.
+    pub persisted_summary: Option<HistorySummary>,
 }
 
 impl Session {
@@ -85,13 +212,22 @@ impl Session {
         Self {
             conversation_history: Vec::new(),
             current_files: HashMap::new(),
+            file_mtimes: HashMap::new(),
+            tool_output_turns: std::env::var("ZARZ_TOOL_OUTPUT_TURNS")
+                .ok()
+                .and_then(|raw| raw.parse::<usize>().ok())
+                .filter(|turns| *turns > 0),
+            context_limit: None,
             pending_changes: Vec::new(),
+            change_baselines: BTreeMap::new(),
+            resumed_file_changes: Vec::new(),
             project_intelligence,
             working_directory,
             storage_id: None,
             title: None,
             created_at: None,
             updated_at: None,
+            persisted_summary: None,
         }
     }
 
@@ -105,11 +241,17 @@ impl Session {
             role,
             content,
             metadata,
+            created_at: Some(Utc::now()),
         });
     }
 
-    #[allow(dead_code)]
+    /// Queue a change for `/apply`, replacing any change already queued for the same path.
     pub fn add_pending_change(&mut self, path: PathBuf, original: String, new_content: String) {
+        if let Some(existing) = self.pending_changes.iter_mut().find(|change| change.path == path) {
+            existing.new_content = new_content;
+            return;
+        }
+
         self.pending_changes.push(PendingChange {
             path,
             original_content: original,
@@ -121,17 +263,163 @@ impl Session {
         self.pending_changes.clear();
     }
 
+    /// Remember what `path` held before a change, unless an earlier change this session
+    /// already did.
+    pub fn note_file_change(&mut self, path: &Path, previous: Option<String>) {
+        self.change_baselines
+            .entry(path.to_path_buf())
+            .or_insert(previous);
+    }
+
+    /// Files this session changed, compared with their baselines on disk now. Files whose
+    /// changes were all undone are left out.
+    pub fn file_changes(&self) -> Vec<FileChangeRecord> {
+        self.change_baselines
+            .iter()
+            .filter_map(|(path, before)| {
+                let after = if path.exists() {
+                    Some(std::fs::read_to_string(path).unwrap_or_default())
+                } else {
+                    None
+                };
+                if *before == after {
+                    return None;
+                }
+
+                let kind = match (before, &after) {
+                    (None, _) => FileChangeKind::Created,
+                    (Some(_), None) => FileChangeKind::Deleted,
+                    (Some(_), Some(_)) => FileChangeKind::Modified,
+                };
+                let before = before.as_deref().unwrap_or_default();
+                let after = after.as_deref().unwrap_or_default();
+                let (mut added, mut removed) = (0, 0);
+                for change in TextDiff::from_lines(before, after).iter_all_changes() {
+                    match change.tag() {
+                        ChangeTag::Insert => added += 1,
+                        ChangeTag::Delete => removed += 1,
+                        ChangeTag::Equal => {}
+                    }
+                }
+
+                Some(FileChangeRecord {
+                    path: path
+                        .strip_prefix(&self.working_directory)
+                        .unwrap_or(path)
+                        .to_path_buf(),
+                    kind,
+                    added,
+                    removed,
+                })
+            })
+            .collect()
+    }
+
+    /// Changes carried over from a resumed session plus this session's, for saving.
+    pub fn all_file_changes(&self) -> Vec<FileChangeRecord> {
+        let current = self.file_changes();
+        let mut all: Vec<FileChangeRecord> = self
+            .resumed_file_changes
+            .iter()
+            .filter(|record| !current.iter().any(|c| c.path == record.path))
+            .cloned()
+            .collect();
+        all.extend(current);
+        all
+    }
+
+    /// Move the session to another project root. Loaded files are dropped since their
+    /// paths were relative to the old root.
+    pub fn set_working_directory(&mut self, working_directory: PathBuf) {
+        self.project_intelligence = ProjectIntelligence::new(working_directory.clone());
+        self.working_directory = working_directory;
+        self.current_files.clear();
+        self.file_mtimes.clear();
+    }
+
     pub fn reset_metadata(&mut self) {
         self.storage_id = None;
         self.title = None;
         self.created_at = None;
         self.updated_at = None;
+        self.persisted_summary = None;
     }
 
     pub fn load_file(&mut self, path: PathBuf, content: String) {
+        if let Some(modified) = self.disk_mtime(&path) {
+            self.file_mtimes.insert(path.clone(), modified);
+        }
         self.current_files.insert(path, content);
     }
 
+    /// Drop a loaded file from context, returning its content.
+    pub fn unload_file(&mut self, path: &Path) -> Option<String> {
+        self.file_mtimes.remove(path);
+        self.current_files.remove(path)
+    }
+
+    fn disk_mtime(&self, path: &PathBuf) -> Option<SystemTime> {
+        std::fs::metadata(self.working_directory.join(path))
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// Re-read loaded files that changed on disk, within the given limits.
+    ///
+    /// Files still being written (modified within the debounce window) wait for a later
+    /// turn, as do changed files beyond the per-turn cap. Oversized files keep their
+    /// previous contents and are not retried until they change again.
+    pub fn reload_changed_files(&mut self, limits: &FileReloadLimits) -> FileReloadReport {
+        let mut report = FileReloadReport::default();
+        let now = SystemTime::now();
+
+        let mut stale: Vec<(PathBuf, SystemTime, SystemTime)> = Vec::new();
+        for path in self.current_files.keys() {
+            let Some(modified) = self.disk_mtime(path) else {
+                continue;
+            };
+            let known = self.file_mtimes.get(path).copied().unwrap_or(SystemTime::UNIX_EPOCH);
+            if modified <= known {
+                continue;
+            }
+            let settled = now
+                .duration_since(modified)
+                .map(|age| age >= limits.debounce)
+                .unwrap_or(false);
+            if !settled {
+                report.deferred.push(path.clone());
+                continue;
+            }
+            stale.push((path.clone(), known, modified));
+        }
+
+        // Longest-stale first so a busy file can't starve the others
+        stale.sort_by_key(|(_, known, _)| *known);
+
+        for (index, (path, _, modified)) in stale.into_iter().enumerate() {
+            if index >= limits.max_files_per_turn {
+                report.deferred.push(path);
+                continue;
+            }
+
+            let full_path = self.working_directory.join(&path);
+            let size = std::fs::metadata(&full_path).map(|meta| meta.len()).unwrap_or(0);
+            if size > limits.max_file_bytes {
+                self.file_mtimes.insert(path.clone(), modified);
+                report.skipped_large.push((path, size));
+                continue;
+            }
+
+            if let Ok(content) = std::fs::read_to_string(&full_path) {
+                self.file_mtimes.insert(path.clone(), modified);
+                self.current_files.insert(path.clone(), content);
+                report.reloaded.push(path);
+            }
+        }
+
+        report
+    }
+
     #[allow(dead_code)]
     pub fn get_file(&self, path: &PathBuf) -> Option<&String> {
         self.current_files.get(path)
@@ -158,7 +446,16 @@ impl Session {
 
         prompt.push_str("Conversation transcript (most recent last):\n\n");
 
-        for message in &self.conversation_history {
+        let window = self.context_window();
+        if window.dropped_messages > 0 {
+            prompt.push_str(&format!(
+                "[{} earlier messages omitted to fit the context window]\n\n",
+                window.dropped_messages
+            ));
+        }
+
+        let cutoff = window.elide_before;
+        for (idx, message) in self.conversation_history.iter().enumerate().skip(window.start) {
             match &message.role {
                 MessageRole::User => {
                     prompt.push_str("User: ");
@@ -176,7 +473,7 @@ impl Session {
                     prompt.push_str(&format!(
                         "Tool[{}.{tool}]: {}",
                         server,
-                        truncate_for_prompt(&message.content, 4000)
+                        truncate_for_prompt(self.prompt_content(idx, cutoff, message), 4000)
                     ));
                 }
             }
@@ -257,6 +554,7 @@ impl Session {
                 },
                 content: "Output:\nERROR: Tool call ended without returning output.".to_string(),
                 metadata: Some(MessageMetadata::for_tool_output(call_id)),
+                created_at: Some(Utc::now()),
             };
 
             inserts.push((state.insert_after, message));
@@ -274,10 +572,113 @@ impl Session {
         }
     }
 
+    /// History index before which tool outputs are elided from prompts.
+    fn tool_output_cutoff(&self) -> usize {
+        let Some(turns) = self.tool_output_turns else {
+            return 0;
+        };
+
+        self.conversation_history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, message)| matches!(message.role, MessageRole::User))
+            .nth(turns - 1)
+            .map(|(idx, _)| idx)
+            .unwrap_or(0)
+    }
+
+    /// Work out which messages fit `context_limit`, eliding the oldest tool outputs
+    /// before dropping the oldest turns.
+    pub fn context_window(&self) -> ContextWindow {
+        let history = &self.conversation_history;
+        let cutoff = self.tool_output_cutoff();
+        let mut window = ContextWindow {
+            elide_before: cutoff,
+            ..ContextWindow::default()
+        };
+
+        let mut total: usize = self
+            .current_files
+            .values()
+            .map(|content| estimate_tokens(content))
+            .sum::<usize>()
+            + history
+                .iter()
+                .enumerate()
+                .map(|(idx, message)| estimate_tokens(self.prompt_content(idx, cutoff, message)))
+                .sum::<usize>();
+
+        let Some(limit) = self.context_limit else {
+            window.estimated_tokens = total;
+            return window;
+        };
+
+        // The latest user message and everything after it are never touched
+        let protected = history
+            .iter()
+            .rposition(|message| matches!(message.role, MessageRole::User))
+            .unwrap_or(history.len());
+
+        while total > limit && window.elide_before < protected {
+            let message = &history[window.elide_before];
+            if Self::is_tool_output(message) {
+                total -= estimate_tokens(&message.content)
+                    .saturating_sub(estimate_tokens(ELIDED_TOOL_OUTPUT));
+            }
+            window.elide_before += 1;
+        }
+
+        while total > limit && window.start < protected {
+            let next = (window.start + 1..protected)
+                .find(|&idx| matches!(history[idx].role, MessageRole::User))
+                .unwrap_or(protected);
+            for (idx, message) in history.iter().enumerate().take(next).skip(window.start) {
+                total -= estimate_tokens(self.prompt_content(idx, window.elide_before, message));
+            }
+            window.start = next;
+        }
+
+        window.dropped_messages = window.start;
+        window.elided_outputs = (window.start.max(cutoff)..window.elide_before)
+            .filter(|&idx| Self::is_tool_output(&history[idx]))
+            .count();
+        window.estimated_tokens = total;
+        window
+    }
+
+    fn is_tool_output(message: &Message) -> bool {
+        matches!(message.role, MessageRole::Tool { .. })
+            && !matches!(
+                message.metadata.as_ref().and_then(|meta| meta.tool_message_kind.as_ref()),
+                Some(ToolMessageKind::Command)
+            )
+    }
+
+    fn prompt_content<'a>(&self, idx: usize, cutoff: usize, message: &'a Message) -> &'a str {
+        if idx < cutoff && Self::is_tool_output(message) {
+            ELIDED_TOOL_OUTPUT
+        } else {
+            &message.content
+        }
+    }
+
     pub fn build_openai_messages(&self) -> Vec<Value> {
         let mut items = Vec::new();
 
-        for message in &self.conversation_history {
+        let window = self.context_window();
+        if window.dropped_messages > 0 {
+            items.push(json!({
+                "role": "system",
+                "content": format!(
+                    "{} earlier messages were omitted to fit the context window.",
+                    window.dropped_messages
+                )
+            }));
+        }
+
+        let cutoff = window.elide_before;
+        for (idx, message) in self.conversation_history.iter().enumerate().skip(window.start) {
             match &message.role {
                 MessageRole::User => {
                     items.push(json!({
@@ -333,19 +734,19 @@ impl Session {
                                 items.push(json!({
                                     "role": "tool",
                                     "tool_call_id": call_id,
-                                    "content": message.content
+                                    "content": self.prompt_content(idx, cutoff, message)
                                 }));
                             } else {
                                 items.push(json!({
                                     "role": "assistant",
-                                    "content": message.content
+                                    "content": self.prompt_content(idx, cutoff, message)
                                 }));
                             }
                         }
                         None => {
                             items.push(json!({
                                 "role": "assistant",
-                                "content": message.content
+                                "content": self.prompt_content(idx, cutoff, message)
                             }));
                         }
                     }
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::conversation_store::ConversationStore;
+use crate::mcp::McpManager;
+
+/// How long MCP servers get to stop before the process exits anyway.
+const MCP_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Exit cleanly when the process receives SIGTERM (from `timeout`, a supervisor, or
+/// `kill`): wait for any session snapshot write to land, stop MCP servers, and
+/// restore the terminal. SIGINT is handled in `interrupt`.
+#[cfg(unix)]
+pub fn install_sigterm_handler(mcp_manager: Option<Arc<McpManager>>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(err) => {
+            eprintln!("Warning: Failed to install SIGTERM handler: {}", err);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        if sigterm.recv().await.is_none() {
+            return;
+        }
+
+        if let Some(manager) = mcp_manager
+            && tokio::time::timeout(MCP_STOP_TIMEOUT, manager.stop_all()).await.is_err()
+        {
+            eprintln!("Warning: Timed out stopping MCP servers");
+        }
+
+        // Sessions are saved after every message; holding the lock means the last
+        // write has finished and no new one can be cut off by exit.
+        let _save_guard = ConversationStore::flush_and_lock();
+
+        restore_terminal();
+        eprintln!("\nReceived SIGTERM; session saved, exiting.");
+        std::process::exit(143);
+    });
+}
+
+#[cfg(not(unix))]
+pub fn install_sigterm_handler(_mcp_manager: Option<Arc<McpManager>>) {}
+
+#[cfg(unix)]
+fn restore_terminal() {
+    use crossterm::{cursor, style::ResetColor, terminal, ExecutableCommand};
+
+    terminal::disable_raw_mode().ok();
+    let mut out = std::io::stdout();
+    out.execute(ResetColor).ok();
+    out.execute(cursor::Show).ok();
+}
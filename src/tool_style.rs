@@ -0,0 +1,118 @@
+use std::io::{stdout, IsTerminal, Write};
+use std::sync::LazyLock;
+
+use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use crossterm::ExecutableCommand;
+
+use crate::cli::ColorChoice;
+
+/// Groups of tools that share a header color and icon in the REPL transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCategory {
+    /// read_file, list_dir, grep_files, search_code
+    Read,
+    /// The bash tool and exec_command
+    Bash,
+    Mcp,
+    /// apply_patch and the other file-changing builtins
+    Patch,
+}
+
+impl ToolCategory {
+    pub fn for_builtin(tool_name: &str) -> Self {
+        match tool_name {
+            "exec_command" | "write_stdin" => ToolCategory::Bash,
+            "apply_patch" | "write_file" | "delete_file" | "move_file" => ToolCategory::Patch,
+            _ => ToolCategory::Read,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            ToolCategory::Read => "read",
+            ToolCategory::Bash => "bash",
+            ToolCategory::Mcp => "mcp",
+            ToolCategory::Patch => "patch",
+        }
+    }
+
+    fn default_style(self) -> ToolStyle {
+        match self {
+            ToolCategory::Read => ToolStyle { color: Color::Blue, icon: "◇".to_string() },
+            ToolCategory::Bash => ToolStyle { color: Color::Cyan, icon: "$".to_string() },
+            ToolCategory::Mcp => ToolStyle { color: Color::Magenta, icon: "⚙".to_string() },
+            ToolCategory::Patch => ToolStyle { color: Color::Green, icon: "✎".to_string() },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ToolStyle {
+    color: Color,
+    icon: String,
+}
+
+const CATEGORIES: [ToolCategory; 4] = [
+    ToolCategory::Read,
+    ToolCategory::Bash,
+    ToolCategory::Mcp,
+    ToolCategory::Patch,
+];
+
+/// Styles in `CATEGORIES` order, with `ZARZ_TOOL_STYLE` overrides applied.
+static STYLES: LazyLock<Vec<ToolStyle>> = LazyLock::new(|| {
+    let mut styles: Vec<ToolStyle> = CATEGORIES.iter().map(|c| c.default_style()).collect();
+    if let Ok(spec) = std::env::var("ZARZ_TOOL_STYLE") {
+        apply_overrides(&mut styles, &spec);
+    }
+    styles
+});
+
+/// Parse `category=color[:icon]` entries separated by commas, e.g.
+/// `bash=yellow:>,mcp=dark_magenta`. Unknown categories or colors are ignored.
+fn apply_overrides(styles: &mut [ToolStyle], spec: &str) {
+    for entry in spec.split(',') {
+        let Some((category, style)) = entry.split_once('=') else {
+            continue;
+        };
+        let Some(index) = CATEGORIES
+            .iter()
+            .position(|c| c.key() == category.trim().to_ascii_lowercase())
+        else {
+            continue;
+        };
+        let (color, icon) = match style.split_once(':') {
+            Some((color, icon)) => (color, Some(icon)),
+            None => (style, None),
+        };
+        if let Ok(color) = Color::try_from(color.trim()) {
+            styles[index].color = color;
+        }
+        if let Some(icon) = icon.map(str::trim).filter(|icon| !icon.is_empty()) {
+            styles[index].icon = icon.to_string();
+        }
+    }
+}
+
+/// Apply `--color`. `auto` leaves color on for terminals unless `NO_COLOR` is set.
+pub fn init_color(choice: ColorChoice) {
+    match choice {
+        ColorChoice::Always => crossterm::style::force_color_output(true),
+        ColorChoice::Never => crossterm::style::force_color_output(false),
+        ColorChoice::Auto => {
+            if !stdout().is_terminal() {
+                crossterm::style::force_color_output(false);
+            }
+        }
+    }
+}
+
+/// Print the one-line header for a tool call: `  <icon> <summary>` in the category color.
+pub fn print_header(category: ToolCategory, summary: &str) {
+    let style = &STYLES[CATEGORIES.iter().position(|c| *c == category).unwrap_or(0)];
+    let mut out = stdout();
+    out.execute(SetForegroundColor(style.color)).ok();
+    println!("  {} {}", style.icon, summary);
+    out.execute(ResetColor).ok();
+    out.flush().ok();
+}
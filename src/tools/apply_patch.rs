@@ -1,12 +1,12 @@
 use std::fs;
-use std::path::{Component, Path, PathBuf};
+use std::path::Path;
 
 use anyhow::{anyhow, Context, Result};
 use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 
-use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+use super::{ensure_parent_dir, resolve_safe_path, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
 
 #[derive(Deserialize)]
 struct ApplyPatchArgs {
@@ -56,23 +56,14 @@ impl ToolHandler for ApplyPatchHandler {
         for block in blocks {
             match block {
                 PatchBlock::Add { path, lines } => {
-                    let resolved = resolve_safe_path(ctx.working_directory, &path)?;
+                    let resolved = resolve_safe_path(ctx.working_directory, &path, "apply_patch")?;
                     ensure_parent_dir(&resolved)?;
-                    let mut content = String::new();
-                    for line in lines {
-                        if let Some(rest) = line.strip_prefix('+') {
-                            content.push_str(rest);
-                        } else {
-                            content.push_str(&line);
-                        }
-                        content.push('\n');
-                    }
-                    fs::write(&resolved, content)
+                    fs::write(&resolved, added_content(&lines))
                         .with_context(|| format!("Failed to write {}", path))?;
                     summary.push(format!("Added {}", path));
                 }
                 PatchBlock::Delete { path } => {
-                    let resolved = resolve_safe_path(ctx.working_directory, &path)?;
+                    let resolved = resolve_safe_path(ctx.working_directory, &path, "apply_patch")?;
                     if resolved.exists() {
                         fs::remove_file(&resolved)
                             .with_context(|| format!("Failed to delete {}", path))?;
@@ -82,13 +73,17 @@ impl ToolHandler for ApplyPatchHandler {
                     }
                 }
                 PatchBlock::Update { path, hunks } => {
-                    let resolved = resolve_safe_path(ctx.working_directory, &path)?;
+                    let resolved = resolve_safe_path(ctx.working_directory, &path, "apply_patch")?;
                     if !resolved.exists() {
                         return Err(anyhow!("Cannot update '{}': file does not exist", path));
                     }
-                    apply_update_patch(&resolved, &hunks)
+                    let notes = apply_update_patch(&resolved, &hunks)
                         .with_context(|| format!("Failed to apply patch to {}", path))?;
-                    summary.push(format!("Updated {}", path));
+                    if notes.is_empty() {
+                        summary.push(format!("Updated {}", path));
+                    } else {
+                        summary.push(format!("Updated {} ({})", path, notes.join("; ")));
+                    }
                 }
             }
         }
@@ -96,36 +91,121 @@ impl ToolHandler for ApplyPatchHandler {
         Ok(ToolExecutionOutput {
             content: summary.join("\n"),
             success: true,
+            file_change: None,
+            image: None,
         })
     }
 }
 
-fn ensure_parent_dir(path: &Path) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directories for {}", path.display()))?;
-    }
-    Ok(())
+/// How far (in lines) a hunk may have drifted from its header position and still apply.
+/// Override with `ZARZ_PATCH_FUZZ`; 0 requires an exact position.
+const DEFAULT_FUZZ_WINDOW: usize = 3;
+
+/// An `*** Update File` block parsed for applying to in-memory contents, as `zarz
+/// rewrite --patch` does before showing its diff.
+pub struct FileUpdate {
+    pub path: String,
+    hunks: Vec<Hunk>,
 }
 
-fn resolve_safe_path(base: &Path, user_path: &str) -> Result<PathBuf> {
-    let relative = Path::new(user_path);
-    if relative.is_absolute() {
-        return Err(anyhow!("Absolute paths are not allowed in apply_patch"));
+impl FileUpdate {
+    /// The patched text and a note per hunk that needed shifting. Hunks are located by
+    /// their context anywhere in the file, nearest the header's line number first, since
+    /// model-written line numbers are rarely exact.
+    pub fn apply(&self, original: &str) -> Result<(String, Vec<String>)> {
+        apply_hunks(original, &self.hunks, original.lines().count())
     }
+}
 
-    for component in relative.components() {
-        if matches!(component, Component::ParentDir | Component::Prefix(_)) {
-            return Err(anyhow!("Parent directory components are not allowed in apply_patch paths"));
-        }
+/// A file a patch would touch, with its contents before and after (`None` once deleted).
+pub struct PlannedFileChange {
+    pub path: String,
+    pub before: String,
+    pub after: Option<String>,
+}
+
+/// What `patch` would do to the files under `working_dir`, without writing anything, so
+/// the change can be shown before it is applied. Fails where applying would.
+pub fn plan_patch(working_dir: &Path, patch: &str) -> Result<Vec<PlannedFileChange>> {
+    let mut changes = Vec::new();
+    for block in parse_patch_blocks(patch)? {
+        let change = match block {
+            PatchBlock::Add { path, lines } => {
+                let resolved = resolve_safe_path(working_dir, &path, "apply_patch")?;
+                PlannedFileChange {
+                    before: fs::read_to_string(&resolved).unwrap_or_default(),
+                    after: Some(added_content(&lines)),
+                    path,
+                }
+            }
+            PatchBlock::Delete { path } => {
+                let resolved = resolve_safe_path(working_dir, &path, "apply_patch")?;
+                PlannedFileChange {
+                    before: fs::read_to_string(&resolved).unwrap_or_default(),
+                    after: None,
+                    path,
+                }
+            }
+            PatchBlock::Update { path, hunks } => {
+                let resolved = resolve_safe_path(working_dir, &path, "apply_patch")?;
+                let before = fs::read_to_string(&resolved)
+                    .with_context(|| format!("Cannot update '{}': file does not exist", path))?;
+                let (after, _) = apply_hunks(&before, &hunks, fuzz_window())
+                    .with_context(|| format!("Failed to apply patch to {}", path))?;
+                PlannedFileChange {
+                    before,
+                    after: Some(after),
+                    path,
+                }
+            }
+        };
+        changes.push(change);
     }
+    Ok(changes)
+}
 
-    Ok(base.join(relative))
+/// The `*** Update File` blocks of `patch`, refusing Add and Delete blocks.
+pub fn parse_file_updates(patch: &str) -> Result<Vec<FileUpdate>> {
+    parse_patch_blocks(patch)?
+        .into_iter()
+        .map(|block| match block {
+            PatchBlock::Update { path, hunks } => Ok(FileUpdate { path, hunks }),
+            PatchBlock::Add { path, .. } => Err(anyhow!("Patch tries to add {}; only updates are allowed", path)),
+            PatchBlock::Delete { path } => Err(anyhow!("Patch tries to delete {}; only updates are allowed", path)),
+        })
+        .collect()
 }
 
-fn apply_update_patch(path: &Path, hunks: &[Hunk]) -> Result<()> {
+/// Apply the hunks to `path`, returning a note for every hunk that only matched after
+/// shifting it or ignoring trailing whitespace.
+fn apply_update_patch(path: &Path, hunks: &[Hunk]) -> Result<Vec<String>> {
     let original = fs::read_to_string(path)
         .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let (new_text, notes) = apply_hunks(&original, hunks, fuzz_window())?;
+    fs::write(path, new_text)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(notes)
+}
+
+fn fuzz_window() -> usize {
+    std::env::var("ZARZ_PATCH_FUZZ")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_FUZZ_WINDOW)
+}
+
+/// The contents of an `*** Add File` block.
+fn added_content(lines: &[String]) -> String {
+    let mut content = String::new();
+    for line in lines {
+        content.push_str(line.strip_prefix('+').unwrap_or(line));
+        content.push('\n');
+    }
+    content
+}
+
+fn apply_hunks(original: &str, hunks: &[Hunk], window: usize) -> Result<(String, Vec<String>)> {
     let original_lines: Vec<String> = if original.is_empty() {
         Vec::new()
     } else {
@@ -137,11 +217,25 @@ fn apply_update_patch(path: &Path, hunks: &[Hunk]) -> Result<()> {
     };
 
     let mut result = Vec::new();
+    let mut notes = Vec::new();
     let mut orig_index: usize = 1;
 
-    for hunk in hunks {
-        let target_start = hunk.start_old.max(1);
-        while orig_index < target_start && orig_index <= original_lines.len() {
+    for (hunk_number, hunk) in hunks.iter().enumerate() {
+        let expected_start = hunk.start_old.max(1);
+        let placement = locate_hunk(&original_lines, hunk, expected_start, orig_index, window)?;
+        if placement.offset != 0 || placement.whitespace_normalized {
+            let mut note = format!(
+                "hunk {} applied at offset {:+}",
+                hunk_number + 1,
+                placement.offset
+            );
+            if placement.whitespace_normalized {
+                note.push_str(" ignoring trailing whitespace");
+            }
+            notes.push(note);
+        }
+
+        while orig_index < placement.start && orig_index <= original_lines.len() {
             result.push(original_lines[orig_index - 1].clone());
             orig_index += 1;
         }
@@ -149,30 +243,10 @@ fn apply_update_patch(path: &Path, hunks: &[Hunk]) -> Result<()> {
         for line in &hunk.lines {
             match line.kind {
                 LineKind::Context => {
-                    let current = original_lines
-                        .get(orig_index - 1)
-                        .ok_or_else(|| anyhow!("Patch context exceeds file length"))?;
-                    if current != &line.text {
-                        return Err(anyhow!(
-                            "Context mismatch while applying patch: expected '{}', found '{}'",
-                            line.text,
-                            current
-                        ));
-                    }
-                    result.push(current.clone());
+                    result.push(original_lines[orig_index - 1].clone());
                     orig_index += 1;
                 }
                 LineKind::Removal => {
-                    let current = original_lines
-                        .get(orig_index - 1)
-                        .ok_or_else(|| anyhow!("Patch removal exceeds file length"))?;
-                    if current != &line.text {
-                        return Err(anyhow!(
-                            "Removal mismatch while applying patch: expected '{}', found '{}'",
-                            line.text,
-                            current
-                        ));
-                    }
                     orig_index += 1;
                 }
                 LineKind::Addition => {
@@ -191,10 +265,102 @@ fn apply_update_patch(path: &Path, hunks: &[Hunk]) -> Result<()> {
     if !result.is_empty() {
         new_text.push('\n');
     }
+    Ok((new_text, notes))
+}
 
-    fs::write(path, new_text)
-        .with_context(|| format!("Failed to write {}", path.display()))?;
-    Ok(())
+struct HunkPlacement {
+    /// 1-based line in the original file where the hunk's old lines begin.
+    start: usize,
+    offset: isize,
+    whitespace_normalized: bool,
+}
+
+/// Find where a hunk's context and removal lines sit in the file: first exactly at the
+/// header position, then within `window` lines of it, then the same again comparing
+/// lines with trailing whitespace stripped. Lines before `min_start` were already
+/// consumed by earlier hunks.
+fn locate_hunk(
+    original_lines: &[String],
+    hunk: &Hunk,
+    expected_start: usize,
+    min_start: usize,
+    window: usize,
+) -> Result<HunkPlacement> {
+    let old_lines: Vec<&str> = hunk
+        .lines
+        .iter()
+        .filter(|line| !matches!(line.kind, LineKind::Addition))
+        .map(|line| line.text.as_str())
+        .collect();
+
+    let mut offsets = vec![0isize];
+    for distance in 1..=window as isize {
+        offsets.push(-distance);
+        offsets.push(distance);
+    }
+
+    for whitespace_normalized in [false, true] {
+        for &offset in &offsets {
+            let start = expected_start as isize + offset;
+            if start < min_start as isize || start < 1 {
+                continue;
+            }
+            let start = start as usize;
+            if block_matches(original_lines, &old_lines, start, whitespace_normalized) {
+                return Ok(HunkPlacement {
+                    start,
+                    offset,
+                    whitespace_normalized,
+                });
+            }
+        }
+    }
+
+    Err(describe_mismatch(original_lines, &old_lines, expected_start, window))
+}
+
+fn block_matches(original_lines: &[String], old_lines: &[&str], start: usize, normalize: bool) -> bool {
+    if start - 1 + old_lines.len() > original_lines.len() {
+        return false;
+    }
+    old_lines
+        .iter()
+        .zip(&original_lines[start - 1..])
+        .all(|(expected, actual)| {
+            if normalize {
+                expected.trim_end() == actual.trim_end()
+            } else {
+                *expected == actual.as_str()
+            }
+        })
+}
+
+/// Report the first differing line at the header position, as the exact matcher did.
+fn describe_mismatch(
+    original_lines: &[String],
+    old_lines: &[&str],
+    expected_start: usize,
+    window: usize,
+) -> anyhow::Error {
+    for (index, expected) in old_lines.iter().enumerate() {
+        match original_lines.get(expected_start - 1 + index) {
+            Some(actual) if actual.trim_end() == expected.trim_end() => continue,
+            Some(actual) => {
+                return anyhow!(
+                    "Context mismatch while applying patch at line {}: expected '{}', found '{}' (no match within ±{} lines)",
+                    expected_start + index,
+                    expected,
+                    actual,
+                    window
+                );
+            }
+            None => return anyhow!("Patch context exceeds file length"),
+        }
+    }
+    anyhow!(
+        "Hunk could not be placed at line {} without overlapping an earlier hunk",
+        expected_start
+    )
 }
 
 fn parse_patch_blocks(input: &str) -> Result<Vec<PatchBlock>> {
@@ -344,3 +510,61 @@ enum LineKind {
     Removal,
     Addition,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunks(body: &str) -> Vec<Hunk> {
+        let lines: Vec<String> = body.lines().map(String::from).collect();
+        parse_hunks(&lines).unwrap()
+    }
+
+    #[test]
+    fn applies_a_hunk_at_its_header_position() {
+        let (text, notes) = apply_hunks("a\nb\nc\n", &hunks("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c"), 3).unwrap();
+        assert_eq!(text, "a\nB\nc\n");
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn finds_a_shifted_hunk_within_the_window() {
+        let original = "new 1\nnew 2\na\nb\nc\n";
+        let (text, notes) = apply_hunks(original, &hunks("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c"), 3).unwrap();
+        assert_eq!(text, "new 1\nnew 2\na\nB\nc\n");
+        assert_eq!(notes, ["hunk 1 applied at offset +2"]);
+    }
+
+    #[test]
+    fn tolerates_trailing_whitespace_differences() {
+        let (text, notes) = apply_hunks("a  \nb\t\nc\n", &hunks("@@ -1,2 +1,2 @@\n a\n-b\n+B"), 3).unwrap();
+        assert_eq!(text, "a  \nB\nc\n");
+        assert_eq!(notes, ["hunk 1 applied at offset +0 ignoring trailing whitespace"]);
+    }
+
+    #[test]
+    fn refuses_a_hunk_outside_the_window() {
+        let original = "x1\nx2\nx3\nx4\nx5\na\nb\nc\n";
+        let err = apply_hunks(original, &hunks("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c"), 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Context mismatch while applying patch at line 1: expected 'a', found 'x1' (no match within ±3 lines)"
+        );
+        // A wider window reaches it
+        assert!(apply_hunks(original, &hunks("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c"), 5).is_ok());
+    }
+
+    #[test]
+    fn reports_context_past_the_end_of_the_file() {
+        let err = apply_hunks("a\nb\n", &hunks("@@ -2,3 +2,3 @@\n b\n-c\n+C\n d"), 0).unwrap_err();
+        assert_eq!(err.to_string(), "Patch context exceeds file length");
+    }
+
+    #[test]
+    fn block_matches_only_normalizes_when_asked() {
+        let original: Vec<String> = ["a ", "b"].map(String::from).to_vec();
+        assert!(!block_matches(&original, &["a", "b"], 1, false));
+        assert!(block_matches(&original, &["a", "b"], 1, true));
+        assert!(!block_matches(&original, &["b", "c"], 2, true));
+    }
+}
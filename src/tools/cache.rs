@@ -0,0 +1,457 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Read-only builtins whose results only depend on their arguments and the file they read.
+const DEFAULT_CACHED_TOOLS: &[&str] = &["read_file", "list_dir", "grep_files"];
+
+/// Which tool results may be reused across turns, and for how long.
+///
+/// Configured through the environment: caching is off unless `ZARZ_TOOL_CACHE=on`,
+/// `ZARZ_TOOL_CACHE_TTL_SECS` sets the lifetime (default 300), `ZARZ_TOOL_CACHE_TOOLS`
+/// opts extra tools in (MCP tools as `server.tool`), and `ZARZ_TOOL_CACHE_SKIP` opts
+/// tools out. Bash, exec and MCP tools are never cached unless listed explicitly.
+#[derive(Debug, Clone)]
+pub struct ToolCacheSettings {
+    pub enabled: bool,
+    pub ttl: Duration,
+    pub tools: Vec<String>,
+}
+
+impl ToolCacheSettings {
+    pub fn from_env() -> Self {
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .map(|raw| {
+                    raw.split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let enabled = matches!(
+            std::env::var("ZARZ_TOOL_CACHE")
+                .map(|raw| raw.trim().to_ascii_lowercase())
+                .as_deref(),
+            Ok("1" | "on" | "true" | "yes")
+        );
+        let ttl_secs = std::env::var("ZARZ_TOOL_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .unwrap_or(300);
+
+        Self {
+            enabled: enabled && ttl_secs > 0,
+            ttl: Duration::from_secs(ttl_secs),
+            tools: cached_tools(list("ZARZ_TOOL_CACHE_TOOLS"), &list("ZARZ_TOOL_CACHE_SKIP")),
+        }
+    }
+}
+
+/// The default cached tools plus `extra`, minus anything in `skip`.
+fn cached_tools(extra: Vec<String>, skip: &[String]) -> Vec<String> {
+    let mut tools: Vec<String> = DEFAULT_CACHED_TOOLS.iter().map(|t| t.to_string()).collect();
+    tools.extend(extra);
+    tools.retain(|tool| !skip.contains(tool));
+    tools.sort();
+    tools.dedup();
+    tools
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToolCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FileStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn capture(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+
+    fn is_current(&self) -> bool {
+        Self::capture(&self.path).as_ref() == Some(self)
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    content: String,
+    stored_at: Instant,
+    stamp: Option<FileStamp>,
+}
+
+/// Content-addressed cache of successful tool results, keyed by tool name and
+/// canonicalized arguments. Entries that read a file are dropped once its mtime or
+/// size changes.
+#[derive(Debug)]
+pub struct ToolResultCache {
+    settings: ToolCacheSettings,
+    entries: HashMap<String, CacheEntry>,
+    hits: u64,
+    misses: u64,
+}
+
+impl ToolResultCache {
+    pub fn new(settings: ToolCacheSettings) -> Self {
+        Self {
+            settings,
+            entries: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(ToolCacheSettings::from_env())
+    }
+
+    /// Return the stored result for an identical earlier call, if it is still valid.
+    pub fn lookup(&mut self, tool: &str, args: &Value, working_dir: &Path) -> Option<String> {
+        if !self.is_cacheable(tool, args, working_dir) {
+            return None;
+        }
+
+        let key = cache_key(tool, args);
+        if self.is_fresh(tool, args, working_dir) {
+            self.hits += 1;
+            self.entries.get(&key).map(|entry| entry.content.clone())
+        } else {
+            self.entries.remove(&key);
+            self.misses += 1;
+            None
+        }
+    }
+
+    /// Whether `lookup` would hit, without counting it as a hit or miss.
+    pub fn is_fresh(&self, tool: &str, args: &Value, working_dir: &Path) -> bool {
+        self.is_cacheable(tool, args, working_dir)
+            && self.entries.get(&cache_key(tool, args)).is_some_and(|entry| {
+                entry.stored_at.elapsed() < self.settings.ttl
+                    && entry.stamp.as_ref().is_none_or(FileStamp::is_current)
+            })
+    }
+
+    pub fn store(&mut self, tool: &str, args: &Value, working_dir: &Path, content: &str) {
+        if !self.is_cacheable(tool, args, working_dir) {
+            return;
+        }
+
+        let stamp = watched_path(tool, args, working_dir).and_then(|path| FileStamp::capture(&path));
+        self.entries.insert(
+            cache_key(tool, args),
+            CacheEntry {
+                content: content.to_string(),
+                stored_at: Instant::now(),
+                stamp,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn stats(&self) -> ToolCacheStats {
+        ToolCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    fn is_cacheable(&self, tool: &str, args: &Value, working_dir: &Path) -> bool {
+        if !self.settings.enabled || !self.settings.tools.iter().any(|t| t == tool) {
+            return false;
+        }
+        // A directory's own mtime only changes when its direct entries are added or
+        // removed, which is enough for a one-level list_dir but not for deeper listings
+        // or a recursive search of file contents.
+        match watched_path(tool, args, working_dir) {
+            Some(path) if path.is_dir() => {
+                tool == "list_dir" && args.get("depth").and_then(Value::as_u64).is_none_or(|depth| depth <= 1)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Results of the read-only calls already made in the current user turn, so an exact
+/// repeat can be answered without running it again. Any call that may have side effects
+/// drops the earlier entries, since what they read may have changed, and is itself
+/// never reused.
+#[derive(Debug, Default)]
+pub struct TurnCallCache {
+    entries: HashMap<String, String>,
+}
+
+impl TurnCallCache {
+    pub fn get(&self, tool: &str, args: &Value) -> Option<&str> {
+        self.entries.get(&cache_key(tool, args)).map(String::as_str)
+    }
+
+    /// Of calls about to run together, the ones that need to run: not answered by an
+    /// earlier call this turn, and not a repeat of one before it in `calls`. Repeats are
+    /// answered from this cache once the first copy has finished.
+    pub fn first_runs<'a, T>(
+        &self,
+        calls: impl IntoIterator<Item = (&'a str, &'a Value, T)>,
+    ) -> Vec<T> {
+        let mut keys = HashSet::new();
+        calls
+            .into_iter()
+            .filter(|(tool, args, _)| {
+                let key = cache_key(tool, args);
+                !self.entries.contains_key(&key) && keys.insert(key)
+            })
+            .map(|(_, _, call)| call)
+            .collect()
+    }
+
+    /// Record a finished call. `content` is `None` for results that should not be
+    /// reused, such as failures.
+    pub fn record(&mut self, tool: &str, args: &Value, read_only: bool, content: Option<&str>) {
+        if !read_only {
+            self.entries.clear();
+            return;
+        }
+        if let Some(content) = content {
+            self.entries.insert(cache_key(tool, args), content.to_string());
+        }
+    }
+}
+
+/// The file or directory a call reads, taken from its `path` argument (list_dir
+/// defaults to the working directory).
+fn watched_path(tool: &str, args: &Value, working_dir: &Path) -> Option<PathBuf> {
+    let path = match args.get("path").and_then(Value::as_str) {
+        Some(path) => Path::new(path),
+        None if tool == "list_dir" => return Some(working_dir.to_path_buf()),
+        None => return None,
+    };
+    Some(if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        working_dir.join(path)
+    })
+}
+
+fn cache_key(tool: &str, args: &Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(tool.as_bytes());
+    hasher.update([0]);
+    hasher.update(canonical_json(args).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Serialize with object keys sorted so argument order does not affect the key.
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonical_json(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache(ttl: Duration) -> ToolResultCache {
+        ToolResultCache::new(ToolCacheSettings {
+            enabled: true,
+            ttl,
+            tools: cached_tools(Vec::new(), &[]),
+        })
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zarz-cache-{}-{}", name, std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn one() {}").unwrap();
+        dir
+    }
+
+    #[test]
+    fn repeated_read_of_an_unchanged_file_hits() {
+        let dir = scratch_dir("hit");
+        let mut cache = cache(Duration::from_secs(300));
+        let args = json!({"path": "src/lib.rs"});
+
+        assert_eq!(cache.lookup("read_file", &args, &dir), None);
+        cache.store("read_file", &args, &dir, "pub fn one() {}");
+        assert_eq!(cache.lookup("read_file", &args, &dir).as_deref(), Some("pub fn one() {}"));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn rewriting_the_file_invalidates_its_entry() {
+        let dir = scratch_dir("rewrite");
+        let mut cache = cache(Duration::from_secs(300));
+        let args = json!({"path": "src/lib.rs"});
+        cache.store("read_file", &args, &dir, "pub fn one() {}");
+
+        fs::write(dir.join("src/lib.rs"), "pub fn one() {}\npub fn two() {}").unwrap();
+
+        assert_eq!(cache.lookup("read_file", &args, &dir), None);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn only_one_level_directory_listings_are_cached() {
+        let dir = scratch_dir("list");
+        let mut cache = cache(Duration::from_secs(300));
+        let shallow = json!({"path": "src"});
+        let deep = json!({"path": "src", "depth": 2});
+
+        cache.store("list_dir", &shallow, &dir, "lib.rs");
+        cache.store("list_dir", &deep, &dir, "lib.rs");
+
+        assert!(cache.lookup("list_dir", &shallow, &dir).is_some());
+        assert_eq!(cache.lookup("list_dir", &deep, &dir), None);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn grep_over_a_directory_is_not_cached() {
+        let dir = scratch_dir("grep");
+        let mut cache = cache(Duration::from_secs(300));
+        let file = json!({"pattern": "fn", "path": "src/lib.rs"});
+        let directory = json!({"pattern": "fn", "path": "src"});
+
+        cache.store("grep_files", &file, &dir, "src/lib.rs:1");
+        cache.store("grep_files", &directory, &dir, "src/lib.rs:1");
+
+        assert!(cache.lookup("grep_files", &file, &dir).is_some());
+        assert_eq!(cache.lookup("grep_files", &directory, &dir), None);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let dir = scratch_dir("ttl");
+        let mut cache = cache(Duration::from_millis(10));
+        let args = json!({"path": "src/lib.rs"});
+        cache.store("read_file", &args, &dir, "pub fn one() {}");
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert_eq!(cache.lookup("read_file", &args, &dir), None);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn skip_list_opts_tools_out_and_extra_tools_opt_in() {
+        let tools = cached_tools(
+            vec!["docs.search".to_string(), "read_file".to_string()],
+            &["grep_files".to_string()],
+        );
+        assert_eq!(tools, vec!["docs.search", "list_dir", "read_file"]);
+
+        let dir = scratch_dir("skip");
+        let mut cache = ToolResultCache::new(ToolCacheSettings {
+            enabled: true,
+            ttl: Duration::from_secs(300),
+            tools,
+        });
+        let args = json!({"pattern": "fn", "path": "src/lib.rs"});
+        cache.store("grep_files", &args, &dir, "src/lib.rs:1");
+        assert_eq!(cache.lookup("grep_files", &args, &dir), None);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn key_ignores_argument_order() {
+        assert_eq!(
+            cache_key("docs.search", &json!({"b": 1, "a": 2})),
+            cache_key("docs.search", &json!({"a": 2, "b": 1}))
+        );
+        assert_ne!(
+            cache_key("docs.search", &json!({"a": 2, "b": 1})),
+            cache_key("docs.search", &json!({"a": 1, "b": 2}))
+        );
+    }
+
+    #[test]
+    fn a_call_with_side_effects_clears_the_turn() {
+        let args = json!({"path": "src/lib.rs"});
+        let mut turn_calls = TurnCallCache::default();
+        turn_calls.record("read_file", &args, true, Some("pub fn one() {}"));
+        assert_eq!(turn_calls.get("read_file", &args), Some("pub fn one() {}"));
+
+        turn_calls.record("bash", &json!({"command": "touch src/lib.rs"}), false, Some(""));
+
+        assert_eq!(turn_calls.get("read_file", &args), None);
+        assert_eq!(turn_calls.get("bash", &json!({"command": "touch src/lib.rs"})), None);
+    }
+
+    #[test]
+    fn identical_calls_in_one_response_run_once() {
+        let main = json!({"path": "src/main.rs"});
+        let readme = json!({"path": "README.md"});
+        let calls = [
+            ("read_file", &main, "call_1"),
+            ("read_file", &main, "call_2"),
+            ("read_file", &readme, "call_3"),
+        ];
+
+        let turn_calls = TurnCallCache::default();
+        let runs = turn_calls.first_runs(calls);
+
+        assert_eq!(runs, vec!["call_1", "call_3"]);
+    }
+
+    #[test]
+    fn calls_answered_earlier_in_the_turn_are_not_run_again() {
+        let main = json!({"path": "src/main.rs"});
+        let readme = json!({"path": "README.md"});
+        let mut turn_calls = TurnCallCache::default();
+        turn_calls.record("read_file", &main, true, Some("fn main() {}"));
+
+        let runs = turn_calls.first_runs([
+            ("read_file", &main, "call_1"),
+            ("read_file", &readme, "call_2"),
+        ]);
+
+        assert_eq!(runs, vec!["call_2"]);
+    }
+}
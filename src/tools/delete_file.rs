@@ -0,0 +1,66 @@
+use std::fs;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+
+use super::{resolve_safe_path, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+
+#[derive(Deserialize)]
+struct DeleteFileArgs {
+    path: String,
+}
+
+pub struct DeleteFileHandler;
+
+impl ToolHandler for DeleteFileHandler {
+    fn name(&self) -> &'static str {
+        "delete_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Delete a file inside the working directory."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file (relative to the working directory)."
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn handle(
+        &self,
+        ctx: ToolExecutionContext<'_>,
+        args: &Value,
+    ) -> Result<ToolExecutionOutput> {
+        let parsed: DeleteFileArgs = serde_json::from_value(args.clone()).map_err(|err| {
+            anyhow!("invalid delete_file arguments: {}", err)
+        })?;
+
+        let resolved = resolve_safe_path(ctx.working_directory, &parsed.path, "delete_file")?;
+        if !resolved.exists() {
+            return Err(anyhow!("Cannot delete '{}': file does not exist", parsed.path));
+        }
+        if resolved.is_dir() {
+            return Err(anyhow!("Cannot delete '{}': path is a directory", parsed.path));
+        }
+
+        fs::remove_file(&resolved)
+            .with_context(|| format!("Failed to delete {}", parsed.path))?;
+
+        Ok(ToolExecutionOutput {
+            content: format!("Deleted {}", parsed.path),
+            success: true,
+            file_change: None,
+            image: None,
+        })
+    }
+}
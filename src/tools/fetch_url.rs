@@ -0,0 +1,416 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+
+use super::{block_on, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+
+/// Characters of page text returned unless `max_chars` asks for less or more.
+const DEFAULT_MAX_CHARS: usize = 20_000;
+/// Upper bound on `max_chars`, so one call can't flood the context.
+const MAX_MAX_CHARS: usize = 100_000;
+/// Bytes downloaded before the rest of the body is dropped.
+const MAX_DOWNLOAD_BYTES: usize = 5 * 1024 * 1024;
+const MAX_REDIRECTS: usize = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Elements whose contents are never readable text.
+static HIDDEN_ELEMENTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<!--.*?-->|<script\b.*?</script\s*>|<style\b.*?</style\s*>|<noscript\b.*?</noscript\s*>|<svg\b.*?</svg\s*>|<template\b.*?</template\s*>|<head\b.*?</head\s*>|<title\b.*?</title\s*>",
+    )
+    .expect("valid regex")
+});
+static TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<title\b[^>]*>(.*?)</title\s*>").expect("valid regex"));
+static LINE_BREAKS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<(?:br|hr|/?p|/?div|/?h[1-6]|/?tr|/?table|/?section|/?article|/?header|/?footer|/?pre|/?blockquote|/?ul|/?ol|/li)\b[^>]*>")
+        .expect("valid regex")
+});
+static LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<li\b[^>]*>").expect("valid regex"));
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").expect("valid regex"));
+static ENTITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);").expect("valid regex"));
+
+#[derive(Deserialize)]
+struct FetchUrlArgs {
+    url: String,
+    #[serde(default)]
+    max_chars: Option<usize>,
+}
+
+/// GETs a public http(s) URL and returns its text, with HTML reduced to readable text.
+/// Only registered when web fetching is enabled in the config.
+pub struct FetchUrlHandler;
+
+impl ToolHandler for FetchUrlHandler {
+    fn name(&self) -> &'static str {
+        "fetch_url"
+    }
+
+    fn description(&self) -> &'static str {
+        "Fetch a public http(s) URL (e.g. documentation) and return its text. HTML is converted to plain text; output is capped at max_chars (default 20000). Private and localhost addresses are refused."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "Absolute http:// or https:// URL."
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Characters of text to return (default 20000, at most 100000)."
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn handle(
+        &self,
+        _ctx: ToolExecutionContext<'_>,
+        args: &Value,
+    ) -> Result<ToolExecutionOutput> {
+        let parsed: FetchUrlArgs = serde_json::from_value(args.clone())
+            .map_err(|err| anyhow!("invalid fetch_url arguments: {}", err))?;
+        let url = Url::parse(parsed.url.trim()).with_context(|| format!("Invalid URL '{}'", parsed.url))?;
+        let max_chars = parsed.max_chars.unwrap_or(DEFAULT_MAX_CHARS).clamp(1, MAX_MAX_CHARS);
+
+        let page = block_on(fetch(url))?;
+        let text = if page.is_html {
+            html_to_text(&page.body)
+        } else {
+            page.body
+        };
+
+        let total_chars = text.chars().count();
+        let mut content = format!("URL: {}\nContent-Type: {}\n\n", page.url, page.content_type);
+        if total_chars > max_chars {
+            content.extend(text.chars().take(max_chars));
+            content.push_str(&format!(
+                "\n... (truncated, {} of {} characters; raise max_chars for more)",
+                max_chars, total_chars
+            ));
+        } else {
+            content.push_str(&text);
+        }
+        if page.body_truncated {
+            content.push_str(&format!("\n... (download stopped at {} bytes)", MAX_DOWNLOAD_BYTES));
+        }
+
+        Ok(ToolExecutionOutput {
+            content,
+            success: true,
+            file_change: None,
+            image: None,
+        })
+    }
+}
+
+struct FetchedPage {
+    url: Url,
+    content_type: String,
+    is_html: bool,
+    body: String,
+    body_truncated: bool,
+}
+
+/// Follow redirects by hand so every hop is checked against the address denylist, and
+/// pin each request to the addresses that were checked so DNS can't be swapped under it.
+async fn fetch(mut url: Url) -> Result<FetchedPage> {
+    let mut addrs = public_addrs(&url)?;
+    for _ in 0..=MAX_REDIRECTS {
+        let host = url.host_str().unwrap_or_default().to_string();
+        let client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            // A proxy would do its own DNS lookup and bypass the pinned addresses
+            .no_proxy()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("zarzcli/", env!("CARGO_PKG_VERSION")))
+            .resolve_to_addrs(&host, &addrs)
+            .build()?;
+        let mut response = client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("Request to {} failed", url))?;
+
+        let status = response.status();
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| anyhow!("{} redirected without a Location header", url))?;
+            (url, addrs) = redirect_target(&url, location)?;
+            continue;
+        }
+        if !status.is_success() {
+            bail!("{} returned HTTP {}", url, status);
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let mime = content_type.to_ascii_lowercase();
+        if !(mime.is_empty()
+            || mime.starts_with("text/")
+            || mime.contains("json")
+            || mime.contains("xml")
+            || mime.contains("javascript"))
+        {
+            bail!("{} is {}, not text", url, content_type);
+        }
+
+        let mut bytes = Vec::new();
+        let mut body_truncated = false;
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > MAX_DOWNLOAD_BYTES {
+                bytes.truncate(MAX_DOWNLOAD_BYTES);
+                body_truncated = true;
+                break;
+            }
+        }
+        let body = String::from_utf8_lossy(&bytes).into_owned();
+        let is_html = mime.contains("html") || (mime.is_empty() && body.trim_start().starts_with('<'));
+        return Ok(FetchedPage {
+            url,
+            content_type: if content_type.is_empty() { "unknown".to_string() } else { content_type },
+            is_html,
+            body,
+            body_truncated,
+        });
+    }
+    bail!("Stopped after {} redirects", MAX_REDIRECTS)
+}
+
+/// Where a redirect from `from` to `location` leads, checked like the original URL.
+fn redirect_target(from: &Url, location: &str) -> Result<(Url, Vec<SocketAddr>)> {
+    let target = from
+        .join(location)
+        .with_context(|| format!("Invalid redirect target '{}'", location))?;
+    let addrs = public_addrs(&target)?;
+    Ok((target, addrs))
+}
+
+/// Resolve the URL's host, refusing non-http(s) schemes and any address that isn't
+/// publicly routable (loopback, private, link-local, ...), to avoid SSRF.
+fn public_addrs(url: &Url) -> Result<Vec<SocketAddr>> {
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("Only http and https URLs can be fetched, not {}", url.scheme());
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("URL {} has no host", url))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = (lookup_host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("Could not resolve {}", host))?
+        .collect();
+    if addrs.is_empty() {
+        bail!("Could not resolve {}", host);
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+        bail!(
+            "Refusing to fetch {}: {} resolves to non-public address {}",
+            url,
+            host,
+            addr.ip()
+        );
+    }
+    Ok(addrs)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_v4(v4);
+            }
+            let segments = ip.segments();
+            // NAT64 (64:ff9b::/96) embeds an IPv4 address in the last 32 bits
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [.., high, low] = segments;
+                return is_public_v4(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low)));
+            }
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (segments[0] & 0xfe00) == 0xfc00 // unique local
+                || (segments[0] & 0xffc0) == 0xfe80 // link-local
+                || (segments[0] & 0xffc0) == 0xfec0 // site-local
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8)) // documentation
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // carrier-grade NAT
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (18..20).contains(&b)) // benchmarking
+        || a >= 240)
+}
+
+/// Reduce an HTML page to its readable text: scripts, styles and markup are dropped,
+/// block elements become line breaks, and entities are decoded.
+fn html_to_text(html: &str) -> String {
+    let title = TITLE
+        .captures(html)
+        .map(|captures| decode_entities(captures[1].trim()))
+        .filter(|title| !title.is_empty());
+
+    let text = HIDDEN_ELEMENTS.replace_all(html, " ");
+    let text = LIST_ITEM.replace_all(&text, "\n- ");
+    let text = LINE_BREAKS.replace_all(&text, "\n");
+    let text = TAG.replace_all(&text, "");
+    let text = decode_entities(&text);
+
+    let mut out = String::new();
+    if let Some(title) = title {
+        out.push_str(&format!("# {}\n\n", title));
+    }
+    let mut blank_run = 0;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |captures: &regex::Captures| {
+            let name = &captures[1];
+            let decoded = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(decimal) = name.strip_prefix('#') {
+                decimal.parse().ok().and_then(char::from_u32)
+            } else {
+                match name {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "mdash" => Some('—'),
+                    "ndash" => Some('–'),
+                    "hellip" => Some('…'),
+                    "copy" => Some('©'),
+                    _ => None,
+                }
+            };
+            decoded.map_or_else(|| captures[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn refuses_private_and_local_ipv4() {
+        for addr in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "172.31.255.255",
+            "192.168.1.1",
+            "100.64.0.1",
+            "169.254.169.254",
+            "0.0.0.0",
+        ] {
+            assert!(!is_public(ip(addr)), "{} should be refused", addr);
+        }
+    }
+
+    #[test]
+    fn refuses_local_ipv6_and_embedded_ipv4() {
+        for addr in ["::1", "::ffff:127.0.0.1", "64:ff9b::7f00:1", "fc00::1", "fd12:3456::1", "fe80::1"] {
+            assert!(!is_public(ip(addr)), "{} should be refused", addr);
+        }
+    }
+
+    #[test]
+    fn allows_public_addresses() {
+        for addr in ["8.8.8.8", "1.1.1.1", "172.32.0.1", "100.128.0.1", "2606:4700:4700::1111", "64:ff9b::808:808"] {
+            assert!(is_public(ip(addr)), "{} should be allowed", addr);
+        }
+    }
+
+    #[test]
+    fn refuses_private_url_hosts_and_other_schemes() {
+        for url in ["http://127.0.0.1:8080/", "http://[::1]/", "http://169.254.169.254/latest/meta-data"] {
+            let err = public_addrs(&Url::parse(url).unwrap()).unwrap_err();
+            assert!(err.to_string().contains("non-public"), "{}: {}", url, err);
+        }
+        let err = public_addrs(&Url::parse("file:///etc/passwd").unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Only http and https"));
+    }
+
+    #[test]
+    fn refuses_redirects_to_private_hosts() {
+        let from = Url::parse("http://8.8.8.8/docs").unwrap();
+        let err = redirect_target(&from, "http://169.254.169.254/latest/meta-data").unwrap_err();
+        assert!(err.to_string().contains("non-public"));
+        assert!(redirect_target(&from, "http://127.0.0.1/admin").is_err());
+
+        let (target, addrs) = redirect_target(&from, "/guide").unwrap();
+        assert_eq!(target.as_str(), "http://8.8.8.8/guide");
+        assert_eq!(addrs, vec!["8.8.8.8:80".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn html_drops_scripts_and_styles() {
+        let html = "<html><head><title>Docs</title><style>body { color: red }</style></head>\
+<body><script>alert('x')</script><p>First</p><ul><li>one</li><li>two</li></ul></body></html>";
+        let text = html_to_text(html);
+        assert!(text.starts_with("# Docs\n"));
+        assert!(text.contains("First") && text.contains("- one") && text.contains("- two"));
+        assert!(!text.contains("alert") && !text.contains("color"));
+    }
+
+    #[test]
+    fn decodes_named_and_numeric_entities() {
+        assert_eq!(decode_entities("a &amp; b &lt;c&gt; &#65;&#x42; &hellip; &unknown;"), "a & b <c> AB … &unknown;");
+    }
+}
@@ -1,17 +1,19 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 
-use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+use super::{exploration_walker, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
 
 #[derive(Deserialize)]
 struct GrepArgs {
     path: String,
     pattern: String,
+    #[serde(default)]
+    include_ignored: bool,
 }
 
 pub struct GrepFilesHandler;
@@ -22,7 +24,7 @@ impl ToolHandler for GrepFilesHandler {
     }
 
     fn description(&self) -> &'static str {
-        "Search for a text pattern inside a single file (simple substring match)."
+        "Search for a text pattern inside a file, or recursively in a directory (simple substring match)."
     }
 
     fn input_schema(&self) -> Value {
@@ -31,11 +33,15 @@ impl ToolHandler for GrepFilesHandler {
             "properties": {
                 "path": {
                     "type": "string",
-                    "description": "File to search (relative to working directory)."
+                    "description": "File or directory to search (relative to working directory)."
                 },
                 "pattern": {
                     "type": "string",
                     "description": "Substring to search for (case-sensitive)."
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "When searching a directory, also search hidden and .gitignore'd files (defaults to false)."
                 }
             },
             "required": ["path", "pattern"]
@@ -53,21 +59,20 @@ impl ToolHandler for GrepFilesHandler {
 
         let full_path = resolve_path(ctx.working_directory, &parsed.path);
         if !full_path.exists() {
-            return Err(anyhow!("File '{}' does not exist", parsed.path));
-        }
-        if full_path.is_dir() {
-            return Err(anyhow!("'{}' is a directory; grep_files expects a file", parsed.path));
+            return Err(anyhow!("Path '{}' does not exist", parsed.path));
         }
-
-        let content = fs::read_to_string(&full_path)
-            .map_err(|err| anyhow!("Failed to read '{}': {}", parsed.path, err))?;
-
-        let mut matches = String::new();
-        for (idx, line) in content.lines().enumerate() {
-            if line.contains(&parsed.pattern) {
-                matches.push_str(&format!("{:>6} | {}\n", idx + 1, line));
+        let matches = if full_path.is_dir() {
+            grep_directory(&full_path, &parsed.pattern, parsed.include_ignored)
+        } else {
+            let content = fs::read_to_string(&full_path)
+                .map_err(|err| anyhow!("Failed to read '{}': {}", parsed.path, err))?;
+            let lines: Vec<&str> = content.lines().collect();
+            let mut matches = String::new();
+            for idx in matching_lines(&lines, &parsed.pattern) {
+                matches.push_str(&format!("{:>6} | {}\n", idx + 1, lines[idx]));
             }
-        }
+            matches
+        };
 
         let output = if matches.is_empty() {
             format!("No matches for '{}' in {}", parsed.pattern, parsed.path)
@@ -78,11 +83,72 @@ impl ToolHandler for GrepFilesHandler {
         Ok(ToolExecutionOutput {
             content: output,
             success: true,
+            file_change: None,
+            image: None,
         })
     }
 }
 
-fn resolve_path(base: &std::path::Path, user_path: &str) -> PathBuf {
+// Caps directory searches so a common pattern cannot flood the context window.
+const MAX_DIRECTORY_MATCHES: usize = 200;
+
+/// Search every readable text file under `root`, reporting `path:line | text`
+/// relative to `root`. Ignored files are skipped unless `include_ignored` is set.
+fn grep_directory(root: &Path, pattern: &str, include_ignored: bool) -> String {
+    let mut matches = String::new();
+    let mut count = 0;
+
+    for (display, content) in text_files(root, include_ignored) {
+        let lines: Vec<&str> = content.lines().collect();
+        for idx in matching_lines(&lines, pattern) {
+            if count == MAX_DIRECTORY_MATCHES {
+                matches.push_str(&format!(
+                    "... stopped after {} matches; narrow the path or pattern\n",
+                    MAX_DIRECTORY_MATCHES
+                ));
+                return matches;
+            }
+            matches.push_str(&format!("{}:{} | {}\n", display, idx + 1, lines[idx]));
+            count += 1;
+        }
+    }
+
+    matches
+}
+
+/// Zero-based indexes of the lines containing `pattern` (case-sensitive substring).
+pub(super) fn matching_lines(lines: &[&str], pattern: &str) -> Vec<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.contains(pattern))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Every readable text file under `root` as `(path relative to root, content)`, in
+/// walk order. Binary and non-UTF-8 files fail to decode and are skipped.
+pub(super) fn text_files(
+    root: &Path,
+    include_ignored: bool,
+) -> impl Iterator<Item = (String, String)> + '_ {
+    exploration_walker(root, include_ignored)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|ft| ft.is_file()))
+        .filter_map(move |entry| {
+            let content = fs::read_to_string(entry.path()).ok()?;
+            let display = entry
+                .path()
+                .strip_prefix(root)
+                .unwrap_or(entry.path())
+                .display()
+                .to_string();
+            Some((display, content))
+        })
+}
+
+pub(super) fn resolve_path(base: &std::path::Path, user_path: &str) -> PathBuf {
     let user = PathBuf::from(user_path);
     if user.is_absolute() {
         user
@@ -1,4 +1,3 @@
-use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
@@ -6,7 +5,7 @@ use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 
-use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+use super::{exploration_walker, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
 
 #[derive(Deserialize)]
 struct ListDirArgs {
@@ -14,6 +13,8 @@ struct ListDirArgs {
     path: String,
     #[serde(default = "default_depth")]
     depth: usize,
+    #[serde(default)]
+    include_ignored: bool,
 }
 
 fn default_path() -> String {
@@ -46,6 +47,10 @@ impl ToolHandler for ListDirHandler {
                 "depth": {
                     "type": "integer",
                     "description": "Optional recursion depth (defaults to 1)."
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "Also list hidden and .gitignore'd entries such as target/ or node_modules/ (defaults to false)."
                 }
             }
         })
@@ -68,11 +73,13 @@ impl ToolHandler for ListDirHandler {
             return Err(anyhow!("'{}' is not a directory", parsed.path));
         }
 
-        let summary = summarize_listing(&target, parsed.depth.max(1))?;
+        let summary = summarize_listing(&target, parsed.depth.max(1), parsed.include_ignored)?;
 
         Ok(ToolExecutionOutput {
             content: summary,
             success: true,
+            file_change: None,
+            image: None,
         })
     }
 }
@@ -86,8 +93,8 @@ fn resolve_path(base: &Path, user_path: &str) -> PathBuf {
     }
 }
 
-fn summarize_listing(path: &Path, depth: usize) -> Result<String> {
-    let entries = collect_entries(path)?;
+fn summarize_listing(path: &Path, depth: usize, include_ignored: bool) -> Result<String> {
+    let entries = collect_entries(path, include_ignored)?;
     if entries.is_empty() {
         return Ok("(directory is empty)".to_string());
     }
@@ -116,13 +123,15 @@ fn summarize_listing(path: &Path, depth: usize) -> Result<String> {
     Ok(summary.join("\n"))
 }
 
-fn collect_entries(path: &Path) -> Result<Vec<Entry>> {
+fn collect_entries(path: &Path, include_ignored: bool) -> Result<Vec<Entry>> {
     let mut entries = Vec::new();
-    for entry in fs::read_dir(path)? {
+    for entry in exploration_walker(path, include_ignored).max_depth(Some(1)).build() {
         let entry = entry?;
-        let metadata = entry.metadata()?;
+        if entry.depth() == 0 {
+            continue;
+        }
         let name = entry.file_name().to_string_lossy().into_owned();
-        let kind = if metadata.is_dir() {
+        let kind = if entry.file_type().is_some_and(|ft| ft.is_dir()) {
             EntryKind::Dir
         } else {
             EntryKind::File
@@ -1,8 +1,9 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use serde_json::json;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::path::Path;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 use crate::unified_exec::UnifiedExecManager;
@@ -11,13 +12,28 @@ mod read_file;
 mod list_dir;
 mod grep_files;
 mod apply_patch;
+mod cache;
+mod dangerous_command;
+mod delete_file;
+mod fetch_url;
+mod move_file;
+mod pdf_text;
+mod search_code;
 mod unified_exec;
+mod write_file;
 
-pub use apply_patch::ApplyPatchHandler;
+pub use apply_patch::{parse_file_updates, plan_patch, ApplyPatchHandler};
+pub use cache::{ToolResultCache, TurnCallCache};
+pub use dangerous_command::dangerous_command;
+pub use delete_file::DeleteFileHandler;
+pub use fetch_url::FetchUrlHandler;
+pub use move_file::MoveFileHandler;
 pub use grep_files::GrepFilesHandler;
 pub use list_dir::ListDirHandler;
 pub use read_file::ReadFileHandler;
+pub use search_code::SearchCodeHandler;
 pub use unified_exec::{ExecCommandHandler, WriteStdinHandler};
+pub use write_file::WriteFileHandler;
 
 pub struct ToolExecutionContext<'a> {
     pub working_directory: &'a Path,
@@ -27,6 +43,22 @@ pub struct ToolExecutionContext<'a> {
 pub struct ToolExecutionOutput {
     pub content: String,
     pub success: bool,
+    /// Set by tools that modify a file so the REPL can render a diff.
+    pub file_change: Option<FileChange>,
+    /// An image for vision-capable models (`read_file` on a PNG, JPEG, GIF or WebP).
+    pub image: Option<ToolImage>,
+}
+
+pub struct ToolImage {
+    pub mime_type: &'static str,
+    /// Base64-encoded image bytes.
+    pub data: String,
+}
+
+pub struct FileChange {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
 }
 
 pub trait ToolHandler: Send + Sync {
@@ -45,16 +77,24 @@ pub struct ToolRegistry {
 }
 
 impl ToolRegistry {
-    pub fn new(unified_exec: Arc<UnifiedExecManager>) -> Self {
+    /// `web_fetch` registers `fetch_url`, the only builtin that makes network requests.
+    pub fn new(unified_exec: Arc<UnifiedExecManager>, web_fetch: bool) -> Self {
         let mut registry = Self {
             handlers: HashMap::new(),
         };
         registry.register(ReadFileHandler);
         registry.register(ListDirHandler);
         registry.register(GrepFilesHandler);
+        registry.register(SearchCodeHandler);
         registry.register(ApplyPatchHandler);
+        registry.register(WriteFileHandler);
+        registry.register(DeleteFileHandler);
+        registry.register(MoveFileHandler);
         registry.register(ExecCommandHandler::new(unified_exec.clone()));
         registry.register(WriteStdinHandler::new(unified_exec));
+        if web_fetch {
+            registry.register(FetchUrlHandler);
+        }
         registry
     }
 
@@ -78,6 +118,12 @@ impl ToolRegistry {
             .collect()
     }
 
+    /// The handler for `tool_name`, for running it outside the registry (e.g. on a
+    /// blocking task).
+    pub fn handler(&self, tool_name: &str) -> Option<Arc<dyn ToolHandler>> {
+        self.handlers.get(tool_name).cloned()
+    }
+
     pub fn execute(
         &self,
         tool_name: &str,
@@ -91,3 +137,56 @@ impl ToolRegistry {
         handler.handle(ctx, args)
     }
 }
+
+/// Directories skipped by exploration tools even without a `.gitignore` entry.
+const DEFAULT_IGNORED_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+/// Walker for exploration tools. Unless `include_ignored` is set it honors
+/// `.gitignore`/`.ignore` files (inside or outside a git repository), skips hidden
+/// entries, and skips `DEFAULT_IGNORED_DIRS`.
+pub(crate) fn exploration_walker(root: &Path, include_ignored: bool) -> ignore::WalkBuilder {
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder.follow_links(false).sort_by_file_name(|a, b| a.cmp(b));
+    if include_ignored {
+        builder.standard_filters(false);
+    } else {
+        builder.require_git(false).filter_entry(|entry| {
+            !(entry.file_type().is_some_and(|ft| ft.is_dir())
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| DEFAULT_IGNORED_DIRS.contains(&name)))
+        });
+    }
+    builder
+}
+
+/// Tool handlers are synchronous but run on a runtime worker thread, where a plain
+/// `Handle::block_on` panics; `block_in_place` hands the worker's other tasks off first.
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+pub(crate) fn ensure_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directories for {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Resolve a model-supplied path inside `base`, rejecting absolute paths and `..`.
+pub(crate) fn resolve_safe_path(base: &Path, user_path: &str, tool: &str) -> Result<PathBuf> {
+    let relative = Path::new(user_path);
+    if relative.is_absolute() {
+        return Err(anyhow!("Absolute paths are not allowed in {}", tool));
+    }
+
+    for component in relative.components() {
+        if matches!(component, Component::ParentDir | Component::Prefix(_)) {
+            return Err(anyhow!("Parent directory components are not allowed in {} paths", tool));
+        }
+    }
+
+    Ok(base.join(relative))
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+
+use super::{ensure_parent_dir, resolve_safe_path, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+
+#[derive(Deserialize)]
+struct MoveFileArgs {
+    from: String,
+    to: String,
+    #[serde(default)]
+    overwrite: bool,
+}
+
+pub struct MoveFileHandler;
+
+impl ToolHandler for MoveFileHandler {
+    fn name(&self) -> &'static str {
+        "move_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Move or rename a file inside the working directory. Refuses to replace an existing destination unless overwrite is true."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "from": {
+                    "type": "string",
+                    "description": "Current path of the file (relative to the working directory)."
+                },
+                "to": {
+                    "type": "string",
+                    "description": "New path for the file (relative to the working directory)."
+                },
+                "overwrite": {
+                    "type": "boolean",
+                    "description": "Replace the destination if it already exists (default false)."
+                }
+            },
+            "required": ["from", "to"]
+        })
+    }
+
+    fn handle(
+        &self,
+        ctx: ToolExecutionContext<'_>,
+        args: &Value,
+    ) -> Result<ToolExecutionOutput> {
+        let parsed: MoveFileArgs = serde_json::from_value(args.clone()).map_err(|err| {
+            anyhow!("invalid move_file arguments: {}", err)
+        })?;
+
+        let source = resolve_safe_path(ctx.working_directory, &parsed.from, "move_file")?;
+        let destination = resolve_safe_path(ctx.working_directory, &parsed.to, "move_file")?;
+
+        if !source.is_file() {
+            return Err(anyhow!("Cannot move '{}': file does not exist", parsed.from));
+        }
+        if destination.is_dir() {
+            return Err(anyhow!("Cannot move to '{}': destination is a directory", parsed.to));
+        }
+        if destination.exists() && !parsed.overwrite {
+            return Err(anyhow!(
+                "Cannot move to '{}': destination exists (pass overwrite: true to replace it)",
+                parsed.to
+            ));
+        }
+
+        ensure_parent_dir(&destination)?;
+        fs::rename(&source, &destination)
+            .with_context(|| format!("Failed to move {} to {}", parsed.from, parsed.to))?;
+
+        Ok(ToolExecutionOutput {
+            content: format!("Moved {} -> {}", parsed.from, parsed.to),
+            success: true,
+            file_change: None,
+            image: None,
+        })
+    }
+}
@@ -0,0 +1,160 @@
+//! Text extraction from PDFs, backed by `pdf-extract`.
+//!
+//! Font encodings and ToUnicode maps are handled by the crate; scanned pages have no
+//! text layer and are reported as such rather than returned empty.
+
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use anyhow::{anyhow, bail, Result};
+
+pub fn extract_text(bytes: &[u8]) -> Result<String> {
+    if !bytes.starts_with(b"%PDF") {
+        bail!("not a PDF file (missing %PDF header)");
+    }
+
+    // pdf-extract panics on some malformed files instead of returning an error; keep the
+    // default hook from printing the panic over the REPL while it's caught
+    let extracted = {
+        let _quiet = QuietPanics::install();
+        panic::catch_unwind(AssertUnwindSafe(|| pdf_extract::extract_text_from_mem(bytes)))
+    }
+    .map_err(|_| anyhow!("the PDF is malformed or uses features that can't be read"))?
+    .map_err(|err| anyhow!("{}", err))?;
+
+    let text = tidy(&extracted);
+    if text.is_empty() {
+        bail!("no text found; the PDF may contain only scanned images");
+    }
+    Ok(text)
+}
+
+/// Silences panic messages from this thread until dropped. Panics on other threads still
+/// go to the hook that was installed before.
+struct QuietPanics;
+
+thread_local! {
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+impl QuietPanics {
+    fn install() -> Self {
+        static HOOK: Once = Once::new();
+        HOOK.call_once(|| {
+            let previous = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                if !QUIET.with(Cell::get) {
+                    previous(info);
+                }
+            }));
+        });
+        QUIET.with(|quiet| quiet.set(true));
+        QuietPanics
+    }
+}
+
+impl Drop for QuietPanics {
+    fn drop(&mut self) {
+        QUIET.with(|quiet| quiet.set(false));
+    }
+}
+
+/// Collapse runs of spaces and blank lines left by text positioning.
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank_run = 0;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-page PDF drawing each line with Helvetica, with a valid xref table.
+    fn fixture(lines: &[&str]) -> Vec<u8> {
+        let mut content = String::from("BT /F1 12 Tf 72 720 Td 14 TL\n");
+        for line in lines {
+            content.push_str(&format!("({}) Tj T*\n", line));
+        }
+        content.push_str("ET");
+
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        ];
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).into_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+        }
+        pdf.extend(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref)
+                .into_bytes(),
+        );
+        pdf
+    }
+
+    #[test]
+    fn extracts_text_in_reading_order() {
+        let text = extract_text(&fixture(&["Release notes", "Version 2.1 fixes the parser"])).unwrap();
+        let first = text.find("Release notes").expect("first line extracted");
+        let second = text.find("Version 2.1 fixes the parser").expect("second line extracted");
+        assert!(first < second);
+    }
+
+    #[test]
+    fn rejects_files_without_a_pdf_header() {
+        let err = extract_text(b"just some text").unwrap_err();
+        assert!(err.to_string().contains("not a PDF"));
+    }
+
+    #[test]
+    fn reports_pages_without_text() {
+        let err = extract_text(&fixture(&[])).unwrap_err();
+        assert!(err.to_string().contains("no text found"));
+    }
+
+    #[test]
+    fn malformed_pdf_is_an_error() {
+        assert!(extract_text(b"%PDF-1.4\nthis is not really a pdf").is_err());
+    }
+
+    #[test]
+    fn panics_are_quiet_only_while_caught() {
+        {
+            let _quiet = QuietPanics::install();
+            assert!(panic::catch_unwind(|| panic!("malformed")).is_err());
+            assert!(QUIET.with(Cell::get));
+        }
+        assert!(!QUIET.with(Cell::get));
+    }
+
+    #[test]
+    fn tidy_collapses_spacing() {
+        assert_eq!(tidy("  a   b \n\n\n\nc  "), "a b\n\nc");
+    }
+}
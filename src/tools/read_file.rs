@@ -2,11 +2,41 @@ use std::fs;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 
-use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+use super::pdf_text;
+use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler, ToolImage};
+
+/// Raw bytes returned for a base64 read; 12 KiB encodes to the same 16K characters
+/// allowed for text.
+const MAX_BASE64_BYTES: usize = 12 * 1024;
+/// Text returned by one read unless `max_bytes` asks for less or more.
+const DEFAULT_MAX_BYTES: usize = 16_000;
+/// Upper bound on `max_bytes`, so one call can't flood the context.
+const MAX_MAX_BYTES: usize = 64 * 1024;
+/// Largest image attached for vision models, measured base64-encoded as sent (Anthropic's
+/// per-image limit).
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Extensions read as images instead of text, with the MIME type sent to the model.
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+];
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Encoding {
+    #[default]
+    Utf8,
+    Base64,
+}
 
 #[derive(Deserialize)]
 struct ReadFileArgs {
@@ -15,6 +45,10 @@ struct ReadFileArgs {
     start_line: Option<usize>,
     #[serde(default)]
     end_line: Option<usize>,
+    #[serde(default)]
+    encoding: Encoding,
+    #[serde(default)]
+    max_bytes: Option<usize>,
 }
 
 pub struct ReadFileHandler;
@@ -25,7 +59,7 @@ impl ToolHandler for ReadFileHandler {
     }
 
     fn description(&self) -> &'static str {
-        "Read the contents of a file. Accepts optional start/end line numbers."
+        "Read the contents of a file. Accepts optional start/end line numbers and a max_bytes cap; the output ends with the file's total line count so large files can be read in ranges. PDFs are returned as extracted text; PNG, JPEG, GIF and WebP images are attached for you to view when the model supports images. Other binary files are rejected unless encoding is \"base64\"."
     }
 
     fn input_schema(&self) -> Value {
@@ -43,6 +77,15 @@ impl ToolHandler for ReadFileHandler {
                 "end_line": {
                     "type": "integer",
                     "description": "Optional ending line number (1-based, inclusive)."
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Maximum bytes of text to return (defaults to 16000, at most 65536). Output is cut at a line boundary and says where to continue."
+                },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["utf8", "base64"],
+                    "description": "Use \"base64\" only when raw bytes of a binary file are needed (capped at 12 KiB). Defaults to \"utf8\"."
                 }
             },
             "required": ["path"]
@@ -62,7 +105,10 @@ impl ToolHandler for ReadFileHandler {
             path,
             start_line,
             end_line,
+            encoding,
+            max_bytes,
         } = parsed;
+        let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_BYTES).clamp(1, MAX_MAX_BYTES);
 
         let full_path = resolve_path(ctx.working_directory, &path);
         if !full_path.exists() {
@@ -72,13 +118,74 @@ impl ToolHandler for ReadFileHandler {
             return Err(anyhow!("'{}' is a directory", path));
         }
 
-        let content = fs::read_to_string(&full_path)
+        let bytes = fs::read(&full_path)
             .map_err(|err| anyhow!("Failed to read '{}': {}", path, err))?;
 
-        let filtered = slice_content(&content, start_line, end_line);
+        if encoding == Encoding::Base64 {
+            if start_line.is_some() || end_line.is_some() || max_bytes != DEFAULT_MAX_BYTES {
+                return Err(anyhow!("start_line/end_line/max_bytes cannot be combined with base64 encoding"));
+            }
+            return Ok(ToolExecutionOutput {
+                content: encode_base64(&bytes),
+                success: true,
+                file_change: None,
+                image: None,
+            });
+        }
+
+        let extension = full_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        if let Some((_, mime_type)) = IMAGE_TYPES.iter().find(|(ext, _)| *ext == extension) {
+            if start_line.is_some() || end_line.is_some() {
+                return Err(anyhow!("start_line/end_line cannot be used with images"));
+            }
+            let encoded_len = bytes.len().div_ceil(3) * 4;
+            if encoded_len > MAX_IMAGE_BYTES {
+                return Err(anyhow!(
+                    "'{}' is {} KB ({} KB base64-encoded); images over {} MB encoded can't be attached",
+                    path,
+                    bytes.len().div_ceil(1024),
+                    encoded_len.div_ceil(1024),
+                    MAX_IMAGE_BYTES / (1024 * 1024)
+                ));
+            }
+            return Ok(ToolExecutionOutput {
+                content: format!("Image {} ({}, {} KB)", path, mime_type, bytes.len().div_ceil(1024)),
+                success: true,
+                file_change: None,
+                image: Some(ToolImage {
+                    mime_type,
+                    data: STANDARD.encode(&bytes),
+                }),
+            });
+        }
+        if extension == "pdf" {
+            let text = pdf_text::extract_text(&bytes)
+                .map_err(|err| anyhow!("Failed to extract text from '{}': {}", path, err))?;
+            return Ok(ToolExecutionOutput {
+                content: slice_content(&text, start_line, end_line, max_bytes),
+                success: true,
+                file_change: None,
+                image: None,
+            });
+        }
+
+        let content = String::from_utf8(bytes).map_err(|_| {
+            anyhow!(
+                "'{}' is not valid UTF-8 (likely a binary file); pass encoding \"base64\" to read its raw bytes",
+                path
+            )
+        })?;
+
+        let filtered = slice_content(&content, start_line, end_line, max_bytes);
         Ok(ToolExecutionOutput {
             content: filtered,
             success: true,
+            file_change: None,
+            image: None,
         })
     }
 }
@@ -92,36 +199,85 @@ fn resolve_path(base: &std::path::Path, user_path: &str) -> PathBuf {
     }
 }
 
-fn slice_content(content: &str, start_line: Option<usize>, end_line: Option<usize>) -> String {
-    let total_lines = content.lines().count();
-    let start = start_line.unwrap_or(1).max(1).min(total_lines.max(1));
-    let mut end = end_line.unwrap_or(total_lines).max(start);
-    end = end.min(total_lines);
+fn encode_base64(bytes: &[u8]) -> String {
+    if bytes.len() <= MAX_BASE64_BYTES {
+        format!("base64 ({} bytes):\n{}", bytes.len(), STANDARD.encode(bytes))
+    } else {
+        format!(
+            "base64 (first {} of {} bytes):\n{}",
+            MAX_BASE64_BYTES,
+            bytes.len(),
+            STANDARD.encode(&bytes[..MAX_BASE64_BYTES])
+        )
+    }
+}
 
-    if start_line.is_none() && end_line.is_none() {
-        return truncate(content);
+/// The requested lines (numbered when a range was given), cut to `max_bytes` at a line
+/// boundary, followed by a footer with the lines shown and the file's total line count.
+fn slice_content(
+    content: &str,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    max_bytes: usize,
+) -> String {
+    let total_lines = content.lines().count();
+    if total_lines == 0 {
+        return "(empty file, 0 lines)".to_string();
     }
+    let start = start_line.unwrap_or(1).max(1);
+    let end = end_line.unwrap_or(total_lines).min(total_lines);
+    if start > end {
+        return format!(
+            "No content in requested range ({}-{}); the file has {} lines",
+            start,
+            end_line.unwrap_or(total_lines),
+            total_lines
+        );
+    }
+    let numbered = start_line.is_some() || end_line.is_some();
 
     let mut buf = String::new();
-    for (idx, line) in content.lines().enumerate() {
+    let mut last_shown = start - 1;
+    let mut cut_line = false;
+    for (idx, line) in content.lines().enumerate().take(end).skip(start - 1) {
         let line_no = idx + 1;
-        if (start..=end).contains(&line_no) {
-            buf.push_str(&format!("{:>6} | {}\n", line_no, line));
+        let formatted = if numbered {
+            format!("{:>6} | {}\n", line_no, line)
+        } else {
+            format!("{}\n", line)
+        };
+        if buf.len() + formatted.len() > max_bytes {
+            if buf.is_empty() {
+                // A single line longer than the cap: show its head rather than nothing
+                buf.push_str(&formatted[..formatted.floor_char_boundary(max_bytes)]);
+                buf.push('\n');
+                last_shown = line_no;
+                cut_line = true;
+            }
+            break;
         }
+        buf.push_str(&formatted);
+        last_shown = line_no;
     }
 
-    if buf.is_empty() {
-        format!("No content in requested range ({}-{})", start, end)
-    } else {
-        truncate(&buf)
-    }
-}
-
-fn truncate(text: &str) -> String {
-    const MAX_CHARS: usize = 16_000;
-    if text.len() <= MAX_CHARS {
-        text.to_string()
+    if cut_line && last_shown == end {
+        buf.push_str(&format!(
+            "... (line {} cut at {} bytes; {} lines in total)",
+            last_shown, max_bytes, total_lines
+        ));
+    } else if last_shown < end {
+        buf.push_str(&format!(
+            "... (truncated at {} bytes; showing lines {}-{} of {}, continue with start_line={})",
+            max_bytes,
+            start,
+            last_shown,
+            total_lines,
+            last_shown + 1
+        ));
+    } else if numbered {
+        buf.push_str(&format!("(lines {}-{} of {})", start, end, total_lines));
     } else {
-        format!("{}\n... (truncated, {} total chars)", &text[..MAX_CHARS], text.len())
+        buf.push_str(&format!("({} lines)", total_lines));
     }
+    buf
 }
@@ -0,0 +1,185 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+
+use super::grep_files::{matching_lines, resolve_path, text_files};
+use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+
+const DEFAULT_CONTEXT_LINES: usize = 3;
+const MAX_CONTEXT_LINES: usize = 10;
+/// Files with matches reported before the search stops.
+const MAX_FILES: usize = 20;
+/// Output size cap. Files that would not fit are counted but not shown, except that
+/// the first file is cut at a line boundary rather than dropped.
+const MAX_OUTPUT_BYTES: usize = 32 * 1024;
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    #[serde(default = "default_path")]
+    path: String,
+    pattern: String,
+    context_lines: Option<usize>,
+    #[serde(default)]
+    include_ignored: bool,
+}
+
+fn default_path() -> String {
+    ".".to_string()
+}
+
+/// `grep_files` plus the surrounding lines of each hit, so the model can usually skip
+/// the follow-up `read_file` calls.
+pub struct SearchCodeHandler;
+
+impl ToolHandler for SearchCodeHandler {
+    fn name(&self) -> &'static str {
+        "search_code"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search for a text pattern in a file or directory and return each match with surrounding lines, file paths and line numbers. Prefer this over grep_files followed by read_file."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to search (relative to working directory, defaults to \".\")."
+                },
+                "pattern": {
+                    "type": "string",
+                    "description": "Substring to search for (case-sensitive)."
+                },
+                "context_lines": {
+                    "type": "integer",
+                    "description": "Lines of context shown before and after each match (defaults to 3, at most 10)."
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "When searching a directory, also search hidden and .gitignore'd files (defaults to false)."
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    fn handle(
+        &self,
+        ctx: ToolExecutionContext<'_>,
+        args: &Value,
+    ) -> Result<ToolExecutionOutput> {
+        let parsed: SearchArgs = serde_json::from_value(args.clone()).map_err(|err| {
+            anyhow!("invalid search_code arguments: {}", err)
+        })?;
+        if parsed.pattern.is_empty() {
+            return Err(anyhow!("search_code needs a non-empty pattern"));
+        }
+        let context = parsed
+            .context_lines
+            .unwrap_or(DEFAULT_CONTEXT_LINES)
+            .min(MAX_CONTEXT_LINES);
+
+        let full_path = resolve_path(ctx.working_directory, &parsed.path);
+        if !full_path.exists() {
+            return Err(anyhow!("Path '{}' does not exist", parsed.path));
+        }
+        let files: Box<dyn Iterator<Item = (String, String)>> = if full_path.is_dir() {
+            Box::new(text_files(&full_path, parsed.include_ignored))
+        } else {
+            let content = fs::read_to_string(&full_path)
+                .map_err(|err| anyhow!("Failed to read '{}': {}", parsed.path, err))?;
+            Box::new(std::iter::once((parsed.path.clone(), content)))
+        };
+
+        let mut output = String::new();
+        let mut files_shown = 0;
+        let mut files_skipped = 0;
+        let mut total_matches = 0;
+        let mut truncated = false;
+
+        for (display, content) in files {
+            let lines: Vec<&str> = content.lines().collect();
+            let hits = matching_lines(&lines, &parsed.pattern);
+            if hits.is_empty() {
+                continue;
+            }
+            total_matches += hits.len();
+            if files_shown == MAX_FILES || truncated {
+                files_skipped += 1;
+                continue;
+            }
+
+            let mut section = format_file_hits(&display, &lines, &hits, context);
+            if output.is_empty() && section.len() > MAX_OUTPUT_BYTES {
+                let cut = section[..section.floor_char_boundary(MAX_OUTPUT_BYTES)]
+                    .rfind('\n')
+                    .map_or(0, |idx| idx + 1);
+                section.truncate(cut);
+                section.push_str("    ... (cut off; narrow the path or pattern)\n\n");
+                truncated = true;
+            } else if output.len() + section.len() > MAX_OUTPUT_BYTES {
+                truncated = true;
+                files_skipped += 1;
+                continue;
+            }
+            output.push_str(&section);
+            files_shown += 1;
+        }
+
+        let content = if total_matches == 0 {
+            format!("No matches for '{}' in {}", parsed.pattern, parsed.path)
+        } else {
+            if files_skipped > 0 {
+                output.push_str(&format!(
+                    "... {} more file(s) with matches not shown ({} matches in total); narrow the path or pattern\n",
+                    files_skipped, total_matches
+                ));
+            }
+            output
+        };
+
+        Ok(ToolExecutionOutput {
+            content,
+            success: true,
+            file_change: None,
+            image: None,
+        })
+    }
+}
+
+/// One file's matches as merged context windows. Matching lines are marked with `>`;
+/// non-adjacent windows are separated by `--`.
+fn format_file_hits(display: &str, lines: &[&str], hits: &[usize], context: usize) -> String {
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    for &hit in hits {
+        let start = hit.saturating_sub(context);
+        let end = (hit + context).min(lines.len() - 1);
+        match windows.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = end,
+            _ => windows.push((start, end)),
+        }
+    }
+
+    let mut section = format!(
+        "{} ({} match{})\n",
+        display,
+        hits.len(),
+        if hits.len() == 1 { "" } else { "es" }
+    );
+    for (idx, (start, end)) in windows.iter().enumerate() {
+        if idx > 0 {
+            section.push_str("    --\n");
+        }
+        for (line_idx, line) in lines.iter().enumerate().take(end + 1).skip(*start) {
+            let marker = if hits.binary_search(&line_idx).is_ok() { '>' } else { ' ' };
+            section.push_str(&format!("{}{:>6} | {}\n", marker, line_idx + 1, line));
+        }
+    }
+    section.push('\n');
+    section
+}
@@ -3,9 +3,8 @@ use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 
-use super::{ToolExecutionContext, ToolExecutionOutput, ToolHandler};
+use super::{block_on, ToolExecutionContext, ToolExecutionOutput, ToolHandler};
 use crate::unified_exec::{ExecCommandRequest, UnifiedExecManager, WriteStdinRequest};
-use tokio::runtime::Handle;
 
 #[derive(Deserialize)]
 struct ExecCommandArgs {
@@ -80,7 +79,7 @@ impl ToolHandler for ExecCommandHandler {
             anyhow!("invalid exec_command arguments: {}", err)
         })?;
 
-        let response = Handle::current().block_on(self.manager.exec_command(ExecCommandRequest {
+        let response = block_on(self.manager.exec_command(ExecCommandRequest {
             command: parsed.cmd,
             shell: parsed.shell,
             login: parsed.login,
@@ -90,6 +89,8 @@ impl ToolHandler for ExecCommandHandler {
         Ok(ToolExecutionOutput {
             content: response.format_for_display(),
             success: true,
+            file_change: None,
+            image: None,
         })
     }
 }
@@ -134,7 +135,7 @@ impl ToolHandler for WriteStdinHandler {
             anyhow!("invalid write_stdin arguments: {}", err)
         })?;
 
-        let response = Handle::current().block_on(self.manager.write_stdin(WriteStdinRequest {
+        let response = block_on(self.manager.write_stdin(WriteStdinRequest {
             session_id: parsed.session_id,
             input: parsed.chars,
             yield_time_ms: parsed.yield_time_ms,
@@ -143,6 +144,8 @@ impl ToolHandler for WriteStdinHandler {
         Ok(ToolExecutionOutput {
             content: response.format_for_display(),
             success: true,
+            file_change: None,
+            image: None,
         })
     }
 }
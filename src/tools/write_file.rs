@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+
+use super::{
+    ensure_parent_dir, resolve_safe_path, FileChange, ToolExecutionContext, ToolExecutionOutput,
+    ToolHandler,
+};
+
+#[derive(Deserialize)]
+struct WriteFileArgs {
+    path: String,
+    content: String,
+}
+
+pub struct WriteFileHandler;
+
+impl ToolHandler for WriteFileHandler {
+    fn name(&self) -> &'static str {
+        "write_file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Create or overwrite a file with the given content (path relative to the working directory)."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file (relative to the working directory)."
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Full contents to write to the file."
+                }
+            },
+            "required": ["path", "content"]
+        })
+    }
+
+    fn handle(
+        &self,
+        ctx: ToolExecutionContext<'_>,
+        args: &Value,
+    ) -> Result<ToolExecutionOutput> {
+        let parsed: WriteFileArgs = serde_json::from_value(args.clone()).map_err(|err| {
+            anyhow!("invalid write_file arguments: {}", err)
+        })?;
+
+        let resolved = resolve_safe_path(ctx.working_directory, &parsed.path, "write_file")?;
+        if resolved.is_dir() {
+            return Err(anyhow!("Cannot write '{}': path is a directory", parsed.path));
+        }
+
+        let existed = resolved.exists();
+        let before = if existed {
+            fs::read_to_string(&resolved)
+                .with_context(|| format!("Failed to read {}", parsed.path))?
+        } else {
+            String::new()
+        };
+
+        if existed && before == parsed.content {
+            return Ok(ToolExecutionOutput {
+                content: format!("No changes for {}", parsed.path),
+                success: true,
+                file_change: None,
+                image: None,
+            });
+        }
+
+        ensure_parent_dir(&resolved)?;
+        fs::write(&resolved, &parsed.content)
+            .with_context(|| format!("Failed to write {}", parsed.path))?;
+
+        let verb = if existed { "Updated" } else { "Created" };
+        Ok(ToolExecutionOutput {
+            content: format!("{} {}", verb, parsed.path),
+            success: true,
+            file_change: Some(FileChange {
+                path: PathBuf::from(&parsed.path),
+                before,
+                after: parsed.content,
+            }),
+            image: None,
+        })
+    }
+}
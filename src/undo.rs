@@ -0,0 +1,238 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::conversation_store::content_digest;
+use crate::tools::resolve_safe_path;
+
+/// Oldest operations are dropped once the stack grows past this.
+const UNDO_LIMIT: usize = 100;
+
+/// Digest stored in `FileSnapshot::written` for a file the change deleted.
+const ABSENT: &str = "absent";
+
+/// What a file looked like before a change; `previous: None` means it did not exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    pub previous: Option<String>,
+    /// Digest of the file right after the change, filled in by `UndoStack::record`.
+    #[serde(default)]
+    pub written: Option<String>,
+}
+
+impl FileSnapshot {
+    /// Snapshot `path` before changing it. Returns `None` for files that exist but
+    /// are not readable text, since they could not be restored faithfully.
+    pub fn capture(path: &Path) -> Option<Self> {
+        let previous = if path.exists() {
+            Some(fs::read_to_string(path).ok()?)
+        } else {
+            None
+        };
+        Some(Self {
+            path: path.to_path_buf(),
+            previous,
+            written: None,
+        })
+    }
+
+    /// Whether the file on disk no longer matches the snapshot.
+    pub fn is_changed(&self) -> bool {
+        let current = if self.path.exists() {
+            fs::read_to_string(&self.path).ok()
+        } else {
+            None
+        };
+        current != self.previous
+    }
+
+    /// Whether the file was edited again after the recorded change, so undoing it
+    /// would also throw those edits away.
+    pub fn edited_since_change(&self) -> bool {
+        self.written
+            .as_ref()
+            .is_some_and(|written| *written != current_digest(&self.path))
+    }
+}
+
+fn current_digest(path: &Path) -> String {
+    if !path.exists() {
+        return ABSENT.to_string();
+    }
+    match fs::read_to_string(path) {
+        Ok(content) => content_digest(&content),
+        // Unreadable now, so it can't be what the change wrote
+        Err(_) => String::new(),
+    }
+}
+
+/// One file operation (a write, a patch, a move) and the files it touched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoEntry {
+    /// Sequence number, also the entry's file name in the undo directory.
+    pub id: u64,
+    pub label: String,
+    pub files: Vec<FileSnapshot>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Snapshots taken before every file change the assistant makes, newest last.
+///
+/// Entries are mirrored to `~/.zarz/undo/<project digest>/`, one JSON file per
+/// operation, so `/undo` still works after restarting the session. They live outside
+/// the project so a checked-out repository can't plant entries of its own.
+pub struct UndoStack {
+    working_dir: PathBuf,
+    /// `None` when there is no home directory; history is then kept in memory only.
+    dir: Option<PathBuf>,
+    entries: Vec<UndoEntry>,
+}
+
+impl UndoStack {
+    pub fn open(working_dir: &Path) -> Self {
+        let canonical = working_dir
+            .canonicalize()
+            .unwrap_or_else(|_| working_dir.to_path_buf());
+        let dir = Config::config_path().ok().and_then(|config_path| {
+            let project = content_digest(&canonical.to_string_lossy());
+            Some(config_path.parent()?.join("undo").join(project))
+        });
+        let entries = dir.as_deref().map(Self::load_entries).unwrap_or_default();
+        Self {
+            working_dir: working_dir.to_path_buf(),
+            dir,
+            entries,
+        }
+    }
+
+    fn load_entries(dir: &Path) -> Vec<UndoEntry> {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut files: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        files
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .filter_map(|raw| serde_json::from_str(&raw).ok())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The snapshot taken before the most recent change to `path`.
+    pub fn latest_snapshot(&self, path: &Path) -> Option<&FileSnapshot> {
+        self.entries
+            .iter()
+            .rev()
+            .flat_map(|entry| entry.files.iter().rev())
+            .find(|snapshot| snapshot.path == path)
+    }
+
+    /// The operations `undo_last` would revert next, newest first.
+    pub fn pending(&self, count: usize) -> impl Iterator<Item = &UndoEntry> {
+        self.entries.iter().rev().take(count)
+    }
+
+    /// Push an operation; call after the change so its result can be fingerprinted.
+    pub fn record(&mut self, label: impl Into<String>, mut files: Vec<FileSnapshot>) {
+        if files.is_empty() {
+            return;
+        }
+        for snapshot in &mut files {
+            snapshot.written = Some(current_digest(&snapshot.path));
+        }
+        let entry = UndoEntry {
+            id: self.entries.last().map(|e| e.id + 1).unwrap_or(1),
+            label: label.into(),
+            files,
+            recorded_at: Utc::now(),
+        };
+        // Undo history is a convenience; failing to persist it must not block the edit
+        if let Err(err) = self.write_entry(&entry) {
+            eprintln!("Warning: Failed to save undo history: {:#}", err);
+        }
+        self.entries.push(entry);
+
+        if self.entries.len() > UNDO_LIMIT {
+            let excess = self.entries.len() - UNDO_LIMIT;
+            let dropped: Vec<u64> = self.entries.drain(..excess).map(|e| e.id).collect();
+            for path in dropped.into_iter().filter_map(|id| self.entry_path(id)) {
+                fs::remove_file(path).ok();
+            }
+        }
+    }
+
+    /// Restore the files of the newest operation and drop it from the stack.
+    /// Nothing is written unless every path in the entry is inside the project.
+    pub fn undo_last(&mut self) -> Result<Option<UndoEntry>> {
+        let Some(entry) = self.entries.last().cloned() else {
+            return Ok(None);
+        };
+        let targets = entry
+            .files
+            .iter()
+            .map(|snapshot| self.checked_path(&snapshot.path))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Restore in reverse so a file touched twice ends at its earliest state
+        for (snapshot, path) in entry.files.iter().zip(&targets).rev() {
+            match &snapshot.previous {
+                Some(content) => {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent).ok();
+                    }
+                    fs::write(path, content)
+                        .with_context(|| format!("Failed to restore {}", path.display()))?;
+                }
+                None => {
+                    if path.exists() {
+                        fs::remove_file(path)
+                            .with_context(|| format!("Failed to remove {}", path.display()))?;
+                    }
+                }
+            }
+        }
+
+        self.entries.pop();
+        if let Some(path) = self.entry_path(entry.id) {
+            fs::remove_file(path).ok();
+        }
+        Ok(Some(entry))
+    }
+
+    /// Re-validate a recorded path against the working directory before touching it.
+    fn checked_path(&self, path: &Path) -> Result<PathBuf> {
+        let Ok(relative) = path.strip_prefix(&self.working_dir) else {
+            bail!(
+                "Refusing to undo: {} is outside {}",
+                path.display(),
+                self.working_dir.display()
+            );
+        };
+        resolve_safe_path(&self.working_dir, &relative.to_string_lossy(), "undo")
+    }
+
+    fn entry_path(&self, id: u64) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{:010}.json", id)))
+    }
+
+    fn write_entry(&self, entry: &UndoEntry) -> Result<()> {
+        let (Some(dir), Some(path)) = (&self.dir, self.entry_path(entry.id)) else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let json = serde_json::to_string(entry).context("Failed to serialize undo entry")?;
+        fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
-use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, PtySize};
 use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Instant};
@@ -54,10 +54,25 @@ impl UnifiedExecResponse {
     }
 }
 
+/// Exited sessions kept around so `/sessions` can still report how they ended.
+const MAX_EXITED_SESSIONS: usize = 20;
+
 struct ExecSession {
+    command: String,
+    started: Instant,
     stdin_tx: mpsc::Sender<Vec<u8>>,
     output_rx: broadcast::Receiver<Vec<u8>>,
     exit_code: Arc<Mutex<Option<i32>>>,
+    killer: std::sync::Mutex<Box<dyn ChildKiller + Send + Sync>>,
+}
+
+/// A snapshot of one exec session, as listed by `/sessions`.
+#[derive(Clone, Debug)]
+pub struct ExecSessionInfo {
+    pub id: i32,
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub elapsed: Duration,
 }
 
 pub struct UnifiedExecManager {
@@ -80,7 +95,7 @@ impl UnifiedExecManager {
         let start = Instant::now();
         let session_id = self.next_id.fetch_add(1, Ordering::SeqCst);
         let (session, reader_handle, writer_handle, wait_handle) =
-            spawn_pty_process(session_id, &request, self.sessions.clone()).await?;
+            spawn_pty_process(&request).await?;
 
         let output = collect_initial_output(&session, request.yield_time_ms.unwrap_or(250)).await;
         let exit_code = session.exit_code.lock().await.clone();
@@ -88,10 +103,11 @@ impl UnifiedExecManager {
         let session_alive = exit_code.is_none();
 
         let session_arc = Arc::new(session);
-        self.sessions
-            .lock()
-            .await
-            .insert(session_id, session_arc);
+        {
+            let mut sessions = self.sessions.lock().await;
+            sessions.insert(session_id, session_arc);
+            prune_exited(&mut sessions).await;
+        }
 
         tokio::spawn(reader_handle);
         tokio::spawn(writer_handle);
@@ -115,6 +131,9 @@ impl UnifiedExecManager {
             map.get(&request.session_id).cloned()
         }
         .ok_or_else(|| anyhow!("Session {} not found", request.session_id))?;
+        if let Some(code) = *session.exit_code.lock().await {
+            return Err(anyhow!("Session {} has already exited with code {}", request.session_id, code));
+        }
 
         session
             .stdin_tx
@@ -126,10 +145,6 @@ impl UnifiedExecManager {
         let exit_code = session.exit_code.lock().await.clone();
         let wall_time = start.elapsed();
 
-        if exit_code.is_some() {
-            self.sessions.lock().await.remove(&request.session_id);
-        }
-
         Ok(UnifiedExecResponse {
             output,
             session_id: if exit_code.is_none() {
@@ -141,12 +156,68 @@ impl UnifiedExecManager {
             wall_time,
         })
     }
+
+    /// Every tracked session, running or recently exited, in start order.
+    pub async fn list_sessions(&self) -> Vec<ExecSessionInfo> {
+        let sessions: Vec<(i32, Arc<ExecSession>)> = self
+            .sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, session)| (*id, session.clone()))
+            .collect();
+
+        let mut infos = Vec::with_capacity(sessions.len());
+        for (id, session) in sessions {
+            infos.push(ExecSessionInfo {
+                id,
+                command: session.command.clone(),
+                exit_code: *session.exit_code.lock().await,
+                elapsed: session.started.elapsed(),
+            });
+        }
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+
+    /// Kill a running session's process. Its exit code is recorded once the process is reaped.
+    pub async fn kill_session(&self, session_id: i32) -> Result<()> {
+        let session = self
+            .sessions
+            .lock()
+            .await
+            .get(&session_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Session {} not found", session_id))?;
+        if let Some(code) = *session.exit_code.lock().await {
+            return Err(anyhow!("Session {} has already exited with code {}", session_id, code));
+        }
+        session
+            .killer
+            .lock()
+            .map_err(|_| anyhow!("Session {} is unavailable", session_id))?
+            .kill()
+            .with_context(|| format!("Failed to kill session {}", session_id))
+    }
+}
+
+/// Drop the oldest exited sessions beyond `MAX_EXITED_SESSIONS`.
+async fn prune_exited(sessions: &mut HashMap<i32, Arc<ExecSession>>) {
+    let mut exited = Vec::new();
+    for (id, session) in sessions.iter() {
+        if session.exit_code.lock().await.is_some() {
+            exited.push(*id);
+        }
+    }
+    exited.sort_unstable();
+    let excess = exited.len().saturating_sub(MAX_EXITED_SESSIONS);
+    for id in &exited[..excess] {
+        sessions.remove(id);
+    }
 }
 
 async fn spawn_pty_process(
-    session_id: i32,
     request: &ExecCommandRequest,
-    registry: Arc<Mutex<HashMap<i32, Arc<ExecSession>>>>,
 ) -> Result<(ExecSession, JoinHandle<()>, JoinHandle<()>, JoinHandle<()>)> {
     let command = build_command(request);
     let pty_system = native_pty_system();
@@ -162,6 +233,7 @@ async fn spawn_pty_process(
 
     let mut child = pair.slave.spawn_command(builder)?;
     let mut killer = child.clone_killer();
+    let session_killer = child.clone_killer();
 
     let (stdin_tx, mut stdin_rx) = mpsc::channel::<Vec<u8>>(128);
     let (output_tx, _) = broadcast::channel::<Vec<u8>>(256);
@@ -194,25 +266,25 @@ async fn spawn_pty_process(
 
     let exit_code = Arc::new(Mutex::new(None));
     let exit_code_wait = exit_code.clone();
-    let registry = registry.clone();
     let wait_handle = tokio::task::spawn_blocking(move || {
         let code = match child.wait() {
             Ok(status) => status.exit_code() as i32,
             Err(_) => -1_i32,
         };
         let _ = killer.kill();
+        // The session stays registered so its exit code can still be listed
         futures::executor::block_on(async {
-            let mut guard = exit_code_wait.lock().await;
-            *guard = Some(code);
-            let mut map = registry.lock().await;
-            map.remove(&session_id);
+            *exit_code_wait.lock().await = Some(code);
         });
     });
 
     let session = ExecSession {
+        command: request.command.clone(),
+        started: Instant::now(),
         stdin_tx,
         output_rx: output_tx.subscribe(),
         exit_code,
+        killer: std::sync::Mutex::new(session_killer),
     };
 
     Ok((session, reader_handle, writer_handle, wait_handle))